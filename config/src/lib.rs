@@ -0,0 +1,576 @@
+//! # Config
+//! The settings of the player, read from `config.toml` in the user's config directory
+//! (a default one is written on the first run), and the themes of the window.
+
+/// The colors and styles of the window
+pub mod theme;
+
+pub use theme::{Palette, Theme};
+
+use game::Action;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    error::Error as StdError,
+    fmt::{self, Display},
+    fs,
+    io,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+/// Most next pieces shown.
+pub const MAX_PREVIEW: usize = 6;
+/// Smallest and biggest scale of the window.
+pub const SCALES: (f32, f32) = (0.25, 4.0);
+/// Longest DAS and ARR, in milliseconds.
+pub const MAX_DELAY: u64 = 1000;
+/// Longest entry and line clear delays, in frames.
+pub const MAX_FRAMES: u32 = 120;
+/// Biggest deadzone of the sticks of the game controllers.
+pub const MAX_DEADZONE: f32 = 0.9;
+/// Loudest volume, in percent.
+pub const MAX_VOLUME: u32 = 100;
+/// Names of the keys the window handles before the players' ones, which can't be bound:
+/// pausing, the settings, the fumens, the full screen and the screenshots.
+pub const RESERVED_KEYS: [&str; 7] = ["Escape", "Tab", "C", "X", "V", "F11", "F12"];
+
+/// Errors which can occur while loading or saving the config.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file isn't valid TOML, has an unknown key or a value of the wrong type.
+    Parse(toml::de::Error),
+    /// A value is out of its range.
+    Invalid(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "{}", err.to_string().trim_end()),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// The settings of the player.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Next pieces shown (0 to `MAX_PREVIEW`).
+    pub preview: usize,
+    /// If `true` the ghost of the current piece is shown.
+    pub ghost: bool,
+    /// If `true` the lines between the cells of the matrix are shown.
+    pub grid: bool,
+    /// Name of the skin of the blocks in the window.
+    pub skin: String,
+    /// Colors of the blocks, replacing the ones of the theme for colorblind players.
+    #[serde(default)]
+    pub palette: Palette,
+    /// If `true` a pattern distinct for each kind of piece is drawn on its blocks
+    /// (so that they aren't told apart by their color only).
+    #[serde(default)]
+    pub patterns: bool,
+    /// If `true` the live stats of the games are shown next to them in the window.
+    #[serde(default = "default_stats")]
+    pub stats: bool,
+    /// Scale of the window when it opens (between the `SCALES`), shrunk to fit the screen.
+    pub scale: f32,
+    /// If `true` the window opens fullscreen.
+    #[serde(default)]
+    pub fullscreen: bool,
+    /// Font of the window's text, `None` for the bundled one.
+    #[serde(default)]
+    pub font: Option<PathBuf>,
+    /// Name of the theme of the window (see `Theme::dir`), `None` for the default one.
+    #[serde(default)]
+    pub theme: Option<String>,
+    pub handling: Handling,
+    #[serde(default)]
+    pub delays: Delays,
+    #[serde(default)]
+    pub volume: Volume,
+    pub bindings: Bindings,
+    /// Buttons of the game controllers, for a config written before them.
+    #[serde(default)]
+    pub controllers: Controllers,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            preview: 5,
+            ghost: true,
+            grid: true,
+            skin: "classic".to_string(),
+            palette: Palette::default(),
+            patterns: false,
+            stats: true,
+            scale: 1.0,
+            fullscreen: false,
+            font: None,
+            theme: None,
+            handling: Handling::default(),
+            delays: Delays::default(),
+            volume: Volume::default(),
+            bindings: Bindings::default(),
+            controllers: Controllers::default(),
+        }
+    }
+}
+
+/// The stats are shown when a config written before them doesn't have the key.
+fn default_stats() -> bool {
+    true
+}
+
+/// How the held keys repeat, in milliseconds (see `game::Handling`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Handling {
+    pub das: u64,
+    pub arr: u64,
+    pub sdf: u32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling::from(game::Handling::default())
+    }
+}
+
+impl From<game::Handling> for Handling {
+    fn from(handling: game::Handling) -> Self {
+        Self {
+            das: handling.das.as_millis() as u64,
+            arr: handling.arr.as_millis() as u64,
+            sdf: handling.sdf,
+        }
+    }
+}
+
+impl From<Handling> for game::Handling {
+    fn from(handling: Handling) -> Self {
+        Self {
+            das: Duration::from_millis(handling.das),
+            arr: Duration::from_millis(handling.arr),
+            sdf: handling.sdf,
+        }
+    }
+}
+
+/// Frames waited after the placements, at 60 frames per second (see `game::Delays`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Delays {
+    pub are: u32,
+    pub line_clear: u32,
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Delays::from(game::Delays::default())
+    }
+}
+
+impl From<game::Delays> for Delays {
+    fn from(delays: game::Delays) -> Self {
+        Self {
+            are: delays.are,
+            line_clear: delays.line_clear,
+        }
+    }
+}
+
+impl From<Delays> for game::Delays {
+    fn from(delays: Delays) -> Self {
+        Self {
+            are: delays.are,
+            line_clear: delays.line_clear,
+        }
+    }
+}
+
+/// Volumes of the sounds of the window, in percent (0 mutes them).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Volume {
+    /// Volume of every sound.
+    pub master: u32,
+    /// Volume of the sound effects, out of the `master` one.
+    pub effects: u32,
+    /// Volume of the music, out of the `master` one.
+    pub music: u32,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self {
+            master: 80,
+            effects: 80,
+            music: 50,
+        }
+    }
+}
+
+/// The keys of the players of the window (the terminal has its own).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bindings {
+    /// Keys of a player playing alone (or online).
+    pub single: Keys,
+    /// Keys of the left player of a versus.
+    pub left: Keys,
+    /// Keys of the right player of a versus.
+    pub right: Keys,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            single: Keys::new([
+                &["Left", "A"],
+                &["Right", "D"],
+                &["Down", "S"],
+                &["Space"],
+                &["Up", "W", "Right Ctrl"],
+                &["Q", "Left Ctrl"],
+            ]),
+            left: Keys::new([&["A"], &["D"], &["S"], &["Space"], &["W"], &["Q"]]),
+            right: Keys::new([&["Left"], &["Right"], &["Down"], &["Right Shift"], &["Up"], &["Right Ctrl"]]),
+        }
+    }
+}
+
+/// The buttons of the game controllers (each one plays for a player, in the order they were
+/// connected) and the deadzone of their left stick, which moves and soft drops.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Controllers {
+    /// Share of the tilt of a stick ignored (0 to `MAX_DEADZONE`).
+    pub deadzone: f32,
+    /// Buttons of the controllers, by SDL's names (e.g. `dpleft` or `a`).
+    pub buttons: Keys,
+    /// Buttons of the controllers with one of these names (as SDL reports it) instead of the
+    /// `buttons`.
+    #[serde(default)]
+    pub pads: BTreeMap<String, Keys>,
+}
+
+impl Default for Controllers {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.5,
+            buttons: Keys::new([&["dpleft"], &["dpright"], &["dpdown"], &["dpup"], &["a", "rightshoulder"], &["b", "leftshoulder"]]),
+            pads: BTreeMap::new(),
+        }
+    }
+}
+
+/// The names of the keys bound to each `Action` (SDL's names, e.g. `Left` or `Right Ctrl`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keys {
+    pub move_left: Vec<String>,
+    pub move_right: Vec<String>,
+    pub soft_drop: Vec<String>,
+    pub hard_drop: Vec<String>,
+    pub rotate_clockwise: Vec<String>,
+    pub rotate_counter_clockwise: Vec<String>,
+}
+
+impl Keys {
+    /// Creates the keys from their names, in the order of `Keys::iter`.
+    fn new(names: [&[&str]; 6]) -> Self {
+        let [move_left, move_right, soft_drop, hard_drop, rotate_clockwise, rotate_counter_clockwise] =
+            names.map(|names| names.iter().map(|name| name.to_string()).collect());
+        Self { move_left, move_right, soft_drop, hard_drop, rotate_clockwise, rotate_counter_clockwise }
+    }
+    /// Returns the names of the keys bound to `action`.
+    pub fn get_mut(&mut self, action: Action) -> &mut Vec<String> {
+        match action {
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::SoftDrop => &mut self.soft_drop,
+            Action::HardDrop => &mut self.hard_drop,
+            Action::RotateClockwise => &mut self.rotate_clockwise,
+            Action::RotateCounterClockwise => &mut self.rotate_counter_clockwise,
+        }
+    }
+    /// Returns each `Action` with the names of its keys and the name of its field.
+    pub fn iter(&self) -> [(Action, &'static str, &[String]); 6] {
+        [
+            (Action::MoveLeft, "move_left", &self.move_left),
+            (Action::MoveRight, "move_right", &self.move_right),
+            (Action::SoftDrop, "soft_drop", &self.soft_drop),
+            (Action::HardDrop, "hard_drop", &self.hard_drop),
+            (Action::RotateClockwise, "rotate_clockwise", &self.rotate_clockwise),
+            (Action::RotateCounterClockwise, "rotate_counter_clockwise", &self.rotate_counter_clockwise),
+        ]
+    }
+}
+
+impl Config {
+    /// Returns the path of the config file, `None` if the system has no config directory.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rusty-tetris").join("config.toml"))
+    }
+    /// Loads the config file, writing the default one if there is none (yet).
+    ///
+    /// The default config is used if the system has no config directory.
+    pub fn load() -> Result<Self, Error> {
+        let Some(path) = Self::path() else { return Ok(Self::default()) };
+        match fs::read_to_string(&path) {
+            Ok(text) => text.parse(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let config = Self::default();
+                config.save()?;
+                Ok(config)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+    /// Loads the theme of the config.
+    pub fn load_theme(&self) -> Result<Theme, Error> {
+        match &self.theme {
+            Some(name) => Theme::load(name),
+            None => Ok(Theme::default()),
+        }
+    }
+    /// Writes the config file.
+    pub fn save(&self) -> Result<(), Error> {
+        let Some(path) = Self::path() else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+    /// Checks that every value is in its range.
+    fn validate(&self) -> Result<(), Error> {
+        let invalid = |message: String| Err(Error::Invalid(message));
+        if self.preview > MAX_PREVIEW {
+            return invalid(format!("invalid preview: {} (expected 0 to {MAX_PREVIEW})", self.preview));
+        }
+        if !(SCALES.0..=SCALES.1).contains(&self.scale) {
+            return invalid(format!("invalid scale: {} (expected {} to {})", self.scale, SCALES.0, SCALES.1));
+        }
+        for (name, delay) in [("das", self.handling.das), ("arr", self.handling.arr)] {
+            if delay > MAX_DELAY {
+                return invalid(format!("invalid handling.{name}: {delay} (expected 0 to {MAX_DELAY} ms)"));
+            }
+        }
+        for (name, frames) in [("are", self.delays.are), ("line_clear", self.delays.line_clear)] {
+            if frames > MAX_FRAMES {
+                return invalid(format!("invalid delays.{name}: {frames} (expected 0 to {MAX_FRAMES} frames)"));
+            }
+        }
+        let volume = self.volume;
+        for (name, percent) in [("master", volume.master), ("effects", volume.effects), ("music", volume.music)] {
+            if percent > MAX_VOLUME {
+                return invalid(format!("invalid volume.{name}: {percent} (expected 0 to {MAX_VOLUME})"));
+            }
+        }
+        let bindings = [("single", &self.bindings.single), ("left", &self.bindings.left), ("right", &self.bindings.right)];
+        for (section, keys) in bindings {
+            for (_, field, names) in keys.iter() {
+                if let Some(name) = names.iter().find(|name| RESERVED_KEYS.iter().any(|key| key.eq_ignore_ascii_case(name))) {
+                    return invalid(format!("invalid bindings.{section}.{field}: {name:?} is reserved (one of {})", RESERVED_KEYS.join(", ")));
+                }
+            }
+        }
+        if !(0.0..=MAX_DEADZONE).contains(&self.controllers.deadzone) {
+            return invalid(format!("invalid controllers.deadzone: {} (expected 0 to {MAX_DEADZONE})", self.controllers.deadzone));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    /// Parses a config file, rejecting unknown keys and values out of their range.
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let config: Self = toml::from_str(text).map_err(Error::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Display for Config {
+    /// Writes the config file, with a comment on each setting.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Rusty Tetris settings")?;
+        writeln!(f)?;
+        writeln!(f, "# next pieces shown (0 to {MAX_PREVIEW})")?;
+        writeln!(f, "preview = {}", self.preview)?;
+        writeln!(f, "# show where the piece would land")?;
+        writeln!(f, "ghost = {}", self.ghost)?;
+        writeln!(f, "# show the lines between the cells of the matrix")?;
+        writeln!(f, "grid = {}", self.grid)?;
+        writeln!(f, "# look of the blocks in the window: \"classic\", \"bevel\" or \"outline\"")?;
+        writeln!(f, "skin = {}", toml::Value::from(self.skin.as_str()))?;
+        let palettes: Vec<_> = Palette::ALL.iter().map(|palette| format!("\"{}\"", palette.name())).collect();
+        writeln!(f, "# colors of the blocks: {} (the theme's)", palettes.join(", "))?;
+        writeln!(f, "palette = \"{}\"", self.palette.name())?;
+        writeln!(f, "# draw a pattern distinct for each piece on its blocks")?;
+        writeln!(f, "patterns = {}", self.patterns)?;
+        writeln!(f, "# show the live stats (pieces per second, attack per minute...) next to the games in the window")?;
+        writeln!(f, "stats = {}", self.stats)?;
+        writeln!(f, "# size of the window when it opens ({} to {}, shrunk to fit the screen)", SCALES.0, SCALES.1)?;
+        writeln!(f, "scale = {:?}", self.scale)?;
+        writeln!(f, "# open the window fullscreen (F11 toggles it)")?;
+        writeln!(f, "fullscreen = {}", self.fullscreen)?;
+        writeln!(f, "# font of the window's text (the bundled one if not set)")?;
+        match &self.font {
+            Some(font) => writeln!(f, "font = {}", toml::Value::from(font.to_string_lossy().as_ref()))?,
+            None => writeln!(f, "# font = \"/path/to/font.ttf\"")?,
+        }
+        writeln!(f, "# theme of the window, a directory in `themes` next to this file (the default one if not set)")?;
+        match &self.theme {
+            Some(theme) => writeln!(f, "theme = {}", toml::Value::from(theme.as_str()))?,
+            None => writeln!(f, "# theme = \"name\"")?,
+        }
+        writeln!(f)?;
+        writeln!(f, "[handling]")?;
+        writeln!(f, "# delayed auto shift: ms a move key is held before the piece shifts on its own")?;
+        writeln!(f, "das = {}", self.handling.das)?;
+        writeln!(f, "# auto repeat rate: ms between two shifts (0 shifts to the wall)")?;
+        writeln!(f, "arr = {}", self.handling.arr)?;
+        writeln!(f, "# soft drop factor: times the soft drop is faster than gravity (0 drops to the floor)")?;
+        writeln!(f, "sdf = {}", self.handling.sdf)?;
+        writeln!(f)?;
+        writeln!(f, "[delays]")?;
+        writeln!(f, "# entry delay: frames (60 per second) between a placement and the next piece")?;
+        writeln!(f, "are = {}", self.delays.are)?;
+        writeln!(f, "# frames the cleared rows flash before the rows above them drop (then the entry delay runs)")?;
+        writeln!(f, "line_clear = {}", self.delays.line_clear)?;
+        writeln!(f)?;
+        writeln!(f, "[volume]")?;
+        writeln!(f, "# volumes of the window in percent (0 to {MAX_VOLUME}, 0 mutes): of every sound,")?;
+        writeln!(f, "# then of the sound effects and of the music out of it")?;
+        writeln!(f, "master = {}", self.volume.master)?;
+        writeln!(f, "effects = {}", self.volume.effects)?;
+        writeln!(f, "music = {}", self.volume.music)?;
+        writeln!(f)?;
+        writeln!(f, "# keys of the window, by SDL name (https://wiki.libsdl.org/SDL2/SDL_Keycode)")?;
+        for (i, (name, keys)) in [
+            ("single", &self.bindings.single),
+            ("left", &self.bindings.left),
+            ("right", &self.bindings.right),
+        ].into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[bindings.{name}]")?;
+            write_keys(f, keys)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "[controllers]")?;
+        writeln!(f, "# share of the tilt of a left stick ignored before it moves the piece (0 to {MAX_DEADZONE})")?;
+        writeln!(f, "deadzone = {:?}", self.controllers.deadzone)?;
+        writeln!(f)?;
+        writeln!(f, "# buttons of the game controllers, by SDL name (a, b, x, y, back, start, leftshoulder, dpup...)")?;
+        writeln!(f, "[controllers.buttons]")?;
+        write_keys(f, &self.controllers.buttons)?;
+        writeln!(f)?;
+        writeln!(f, "# buttons of the controllers with a name (printed when one is connected) instead of the ones above")?;
+        if self.controllers.pads.is_empty() {
+            writeln!(f, "# [controllers.pads.\"Xbox Series X Controller\"]")?;
+        }
+        for (i, (name, keys)) in self.controllers.pads.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[controllers.pads.{}]", toml::Value::from(name.as_str()))?;
+            write_keys(f, keys)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the names of the keys (or buttons) bound to each action, one action per line.
+fn write_keys(f: &mut fmt::Formatter<'_>, keys: &Keys) -> fmt::Result {
+    for (_, action, names) in keys.iter() {
+        let names = toml::Value::Array(names.iter().map(|name| name.as_str().into()).collect());
+        writeln!(f, "{action} = {names}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn written_config_reads_back() {
+        let config = Config::default();
+        let read: Config = config.to_string().parse().expect("the written config is valid");
+        assert_eq!(read.to_string(), config.to_string());
+    }
+
+    #[test]
+    fn reserved_keys_are_rejected() {
+        for key in RESERVED_KEYS {
+            for section in ["single", "left", "right"] {
+                let mut config = Config::default();
+                let bindings = match section {
+                    "single" => &mut config.bindings.single,
+                    "left" => &mut config.bindings.left,
+                    _ => &mut config.bindings.right,
+                };
+                bindings.get_mut(Action::HardDrop).push(key.to_lowercase());
+                let Err(Error::Invalid(message)) = config.validate() else {
+                    panic!("{key} is accepted in bindings.{section}");
+                };
+                assert!(message.contains(&format!("bindings.{section}.hard_drop")), "{message}");
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_keys_are_rejected_in_the_file() {
+        let text = Config::default().to_string().replace("hard_drop = [\"Space\"]", "hard_drop = [\"Tab\"]");
+        assert!(text.contains("\"Tab\""), "the default config binds Space to hard_drop");
+        assert!(matches!(text.parse::<Config>(), Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn controller_buttons_may_share_names_with_reserved_keys() {
+        let mut config = Config::default();
+        config.controllers.buttons.get_mut(Action::HardDrop).push("x".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn values_out_of_range_are_rejected() {
+        let configs = [
+            Config { preview: MAX_PREVIEW + 1, ..Config::default() },
+            Config { scale: SCALES.1 * 2.0, ..Config::default() },
+            Config { handling: Handling { das: MAX_DELAY + 1, ..Handling::default() }, ..Config::default() },
+            Config { delays: Delays { are: MAX_FRAMES + 1, ..Delays::default() }, ..Config::default() },
+            Config { volume: Volume { music: MAX_VOLUME + 1, ..Volume::default() }, ..Config::default() },
+            Config { controllers: Controllers { deadzone: 1.0, ..Controllers::default() }, ..Config::default() },
+        ];
+        for config in configs {
+            assert!(matches!(config.validate(), Err(Error::Invalid(_))), "{config}");
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{Config, Error};
+use matrix::Block;
+use pieces::{Rgb, TetrominoKind as Kind};
+use serde::{Deserialize, Deserializer, de};
+use std::{
+    fs,
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// Name of the file of a theme, in its directory.
+const FILE: &str = "theme.toml";
+/// Name of the block textures of a theme, in its directory (see `Theme::atlas`).
+const ATLAS: &str = "blocks.png";
+
+/// The colors and styles of the window, read from a theme directory.
+///
+/// Every table and value is optional, the missing ones are the default theme's.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Theme {
+    pub colors: Colors,
+    pub background: Background,
+    pub grid: Grid,
+    pub ghost: Ghost,
+    pub hud: Hud,
+    /// PNG of the block textures, `None` if the theme has none (the skin draws the blocks).
+    ///
+    /// It has a row of square tiles for each block: I, J, L, O, S, Z, T then garbage. A row has
+    /// either one tile, or 16 for connected blocks: the tile of a block is then the one at the
+    /// bits of its `Links` (up 1, right 2, down 4, left 8), which draws the outline of its
+    /// tetromino around all its blocks.
+    #[serde(skip)]
+    pub atlas: Option<PathBuf>,
+}
+
+/// Color of each block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Colors {
+    #[serde(deserialize_with = "hex")]
+    pub i: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub j: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub l: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub o: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub s: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub z: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub t: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub garbage: Rgb,
+}
+
+/// Colors of the blocks for colorblind players, replacing the ones of the theme.
+///
+/// The pieces confused with each other differ in lightness too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// The colors of the theme.
+    #[default]
+    Theme,
+    /// For players who can't see green well (red and green look alike).
+    Deuteranopia,
+    /// For players who can't see red well (red looks dark and like green).
+    Protanopia,
+    /// For players who can't see blue well (blue looks like green, yellow like pink).
+    Tritanopia,
+    /// Bright colors far from each other.
+    HighContrast,
+}
+
+/// Colors behind the blocks and the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Background {
+    /// Around the matrix and the panels (and the bars around the layout).
+    #[serde(deserialize_with = "hex")]
+    pub window: Rgb,
+    #[serde(deserialize_with = "hex")]
+    pub matrix: Rgb,
+    /// Behind the score and the next pieces.
+    #[serde(deserialize_with = "hex")]
+    pub panels: Rgb,
+}
+
+/// How the lines between the cells of the matrix are drawn (if the config shows them),
+/// black lines by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Grid {
+    pub style: GridStyle,
+    #[serde(deserialize_with = "hex")]
+    pub color: Rgb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GridStyle {
+    /// Lines around every cell.
+    #[default]
+    Lines,
+    /// A dot at each corner of the cells.
+    Dots,
+}
+
+/// How the ghost of the current piece is drawn (if the config shows it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Ghost {
+    pub style: GhostStyle,
+    /// Opacity of the ghost, from 0 (invisible) to 255.
+    pub alpha: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GhostStyle {
+    /// Cells filled with the color of the piece.
+    #[default]
+    Transparent,
+    /// The border of the cells in the color of the piece.
+    Outline,
+    /// The blocks of the piece, as they are drawn in the matrix.
+    Block,
+}
+
+/// Colors of the text and the indicators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Hud {
+    #[serde(deserialize_with = "hex")]
+    pub text: Rgb,
+    /// Bar of the incoming garbage of a versus.
+    #[serde(deserialize_with = "hex")]
+    pub incoming: Rgb,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            i: (0, 231, 254),     // LIGHT BLUE
+            j: (0, 0, 255),       // BLUE
+            l: (255, 127, 0),     // ORANGE
+            o: (255, 200, 0),     // YELLOW
+            s: (0, 255, 0),       // GREEN
+            z: (255, 0, 0),       // RED
+            t: (255, 0, 255),     // MAGENTA
+            garbage: (150, 150, 150),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self {
+            window: (0, 0, 0),
+            matrix: (50, 50, 50),
+            panels: (50, 50, 50),
+        }
+    }
+}
+
+impl Default for Ghost {
+    fn default() -> Self {
+        Self {
+            style: GhostStyle::default(),
+            alpha: 40,
+        }
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self {
+            text: (255, 255, 255),
+            incoming: (255, 0, 0),
+        }
+    }
+}
+
+impl Colors {
+    /// Returns the color of the tetrominos of `kind`.
+    pub fn piece(&self, kind: Kind) -> Rgb {
+        match kind {
+            Kind::I => self.i,
+            Kind::J => self.j,
+            Kind::L => self.l,
+            Kind::O => self.o,
+            Kind::S => self.s,
+            Kind::Z => self.z,
+            Kind::T => self.t,
+        }
+    }
+    /// Returns the color of `block`.
+    pub fn block(&self, block: Block) -> Rgb {
+        match block {
+            Block::Piece(kind, _) => self.piece(kind),
+            Block::Garbage => self.garbage,
+        }
+    }
+}
+
+impl Palette {
+    /// Every palette, in the order the settings cycle through them.
+    pub const ALL: [Self; 5] = [Self::Theme, Self::Deuteranopia, Self::Protanopia, Self::Tritanopia, Self::HighContrast];
+
+    /// Returns the name of the palette in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Theme => "theme",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Protanopia => "protanopia",
+            Self::Tritanopia => "tritanopia",
+            Self::HighContrast => "high-contrast",
+        }
+    }
+    /// Returns the colors of the blocks, the ones of `theme` for `Theme`.
+    pub fn colors(self, theme: &Theme) -> Colors {
+        let garbage = theme.colors.garbage;
+        match self {
+            Self::Theme => theme.colors,
+            // Okabe and Ito's palette, with S and Z far apart in lightness
+            Self::Deuteranopia => Colors {
+                i: (86, 180, 233),    // SKY BLUE
+                j: (0, 80, 170),      // DARK BLUE
+                l: (230, 159, 0),     // ORANGE
+                o: (240, 228, 66),    // YELLOW
+                s: (160, 230, 210),   // PALE TEAL
+                z: (150, 60, 0),      // DARK VERMILION
+                t: (204, 121, 167),   // REDDISH PURPLE
+                garbage,
+            },
+            Self::Protanopia => Colors {
+                i: (86, 180, 233),    // SKY BLUE
+                j: (0, 80, 170),      // DARK BLUE
+                l: (240, 180, 40),    // LIGHT ORANGE
+                o: (250, 240, 120),   // PALE YELLOW
+                s: (0, 130, 100),     // DARK TEAL
+                z: (255, 140, 90),    // SALMON
+                t: (190, 140, 220),   // LAVENDER
+                garbage,
+            },
+            Self::Tritanopia => Colors {
+                i: (0, 200, 210),     // CYAN
+                j: (20, 40, 120),     // NAVY
+                l: (255, 120, 0),     // ORANGE
+                o: (255, 255, 255),   // WHITE
+                s: (0, 150, 70),      // GREEN
+                z: (200, 0, 40),      // CRIMSON
+                t: (255, 130, 200),   // PINK
+                garbage,
+            },
+            Self::HighContrast => Colors {
+                i: (255, 255, 255),   // WHITE
+                j: (30, 100, 255),    // BLUE
+                l: (255, 150, 0),     // ORANGE
+                o: (255, 255, 0),     // YELLOW
+                s: (0, 255, 160),     // SPRING GREEN
+                z: (200, 0, 0),       // DARK RED
+                t: (190, 60, 255),    // VIOLET
+                garbage: (110, 110, 110),
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the directory of the theme called `name`: `themes/<name>` next to the config file.
+    pub fn dir(name: &str) -> Option<PathBuf> {
+        Some(Config::path()?.parent()?.join("themes").join(name))
+    }
+    /// Loads the theme called `name` (see `Theme::dir`) with its block textures, if any.
+    pub fn load(name: &str) -> Result<Self, Error> {
+        let Some(dir) = Self::dir(name) else {
+            return Err(Error::Invalid("no config directory to load the themes from".to_string()));
+        };
+        let mut theme: Self = fs::read_to_string(dir.join(FILE))?.parse()?;
+        theme.atlas = Some(dir.join(ATLAS)).filter(|atlas| atlas.is_file());
+        Ok(theme)
+    }
+}
+
+impl FromStr for Theme {
+    type Err = Error;
+
+    /// Parses a theme file, rejecting unknown keys and invalid colors.
+    fn from_str(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(Error::Parse)
+    }
+}
+
+/// Deserializes a color written `"#rrggbb"`.
+fn hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    let invalid = || de::Error::invalid_value(de::Unexpected::Str(&text), &"a color written \"#rrggbb\"");
+    let digits = text.strip_prefix('#').filter(|digits| digits.len() == 6).ok_or_else(invalid)?;
+    let channel = |i: usize| u8::from_str_radix(digits.get(i..i + 2).ok_or_else(invalid)?, 16).map_err(|_| invalid());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,233 @@
+use crate::music::Music;
+use config::{Volume, MAX_VOLUME};
+use sdl2::{
+    Sdl,
+    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
+};
+
+/// Samples per second asked to the audio device.
+const RATE: i32 = 44_100;
+/// Sound effects played at the same time at most (the oldest one is cut).
+const MAX_VOICES: usize = 16;
+
+/// A sound effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Sound {
+    Move,
+    Rotate,
+    Lock,
+    /// Rows cleared at once.
+    Clear(u32),
+    TSpin,
+    /// Consecutive placements clearing rows (1 for the second one).
+    Combo(u32),
+    LevelUp,
+    GameOver,
+    /// The goal of the mode was reached.
+    Completed,
+}
+
+/// Shape of the wave of a tone.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Wave {
+    /// A pulse wave, high for this share of its period.
+    Pulse(f32),
+    Triangle,
+    /// Random values, changing at the frequency of the tone.
+    Noise,
+}
+
+impl Wave {
+    /// Returns the value of the wave at `phase` (0 to 1) of its period, from -1 to 1
+    /// (`noise` is the current random value).
+    pub(crate) fn at(self, phase: f32, noise: f32) -> f32 {
+        match self {
+            Self::Pulse(duty) => if phase < duty { 1.0 } else { -1.0 },
+            Self::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Self::Noise => noise,
+        }
+    }
+}
+
+/// A tone of a sound effect, swept from a frequency to another as it fades out.
+#[derive(Debug, Clone, Copy)]
+struct Tone {
+    wave: Wave,
+    /// Frequencies at its start and at its end, in Hz.
+    from: f32,
+    to: f32,
+    /// Length in seconds.
+    length: f32,
+    volume: f32,
+}
+
+/// Returns a tone of `wave` at `frequency`, `length` seconds long.
+const fn note(wave: Wave, frequency: f32, length: f32, volume: f32) -> Tone {
+    Tone { wave, from: frequency, to: frequency, length, volume }
+}
+
+/// Returns the frequency of the note `semitones` above the A at 440 Hz (below if negative).
+pub(crate) fn frequency(semitones: i32) -> f32 {
+    440.0 * 2f32.powf(semitones as f32 / 12.0)
+}
+
+impl Sound {
+    /// Returns the tones of the sound, played one after the other.
+    fn tones(self) -> Vec<Tone> {
+        const SQUARE: Wave = Wave::Pulse(0.5);
+        const THIN: Wave = Wave::Pulse(0.25);
+        match self {
+            Self::Move => vec![note(THIN, 1760.0, 0.015, 0.12)],
+            Self::Rotate => vec![Tone { wave: THIN, from: 880.0, to: 1320.0, length: 0.035, volume: 0.12 }],
+            Self::Lock => vec![Tone { wave: Wave::Triangle, from: 220.0, to: 55.0, length: 0.07, volume: 0.6 }],
+            // a rising arpeggio, one note longer per row
+            Self::Clear(lines) => (0..=lines.min(4))
+                .map(|i| note(SQUARE, frequency([3, 7, 10, 15, 19][i as usize]), if i == 4 { 0.2 } else { 0.06 }, 0.2))
+                .collect(),
+            Self::TSpin => vec![
+                Tone { wave: THIN, from: 300.0, to: 1200.0, length: 0.12, volume: 0.2 },
+                note(THIN, 1200.0, 0.08, 0.2),
+            ],
+            // a semitone higher for each placement of the combo
+            Self::Combo(combo) => {
+                let base = frequency(combo.min(24) as i32);
+                vec![note(Wave::Triangle, base, 0.05, 0.4), note(Wave::Triangle, base * 1.5, 0.08, 0.4)]
+            }
+            Self::LevelUp => [3, 7, 10, 15].into_iter().map(|semitones| note(SQUARE, frequency(semitones), 0.08, 0.18)).collect(),
+            Self::GameOver => [-2, -3, -4, -5]
+                .into_iter()
+                .map(|semitones| note(SQUARE, frequency(semitones), 0.18, 0.18))
+                .chain([Tone { wave: Wave::Noise, from: 2000.0, to: 200.0, length: 0.6, volume: 0.25 }])
+                .collect(),
+            Self::Completed => [3, 7, 10, 15, 10, 15]
+                .into_iter()
+                .enumerate()
+                .map(|(i, semitones)| note(SQUARE, frequency(semitones), if i == 5 { 0.4 } else { 0.1 }, 0.18))
+                .collect(),
+        }
+    }
+}
+
+/// A sound effect being played.
+struct Voice {
+    tones: Vec<Tone>,
+    /// Index of the tone played.
+    tone: usize,
+    /// Samples of the tone played.
+    played: u32,
+    /// Phase of the wave (0 to 1).
+    phase: f32,
+}
+
+/// Mixes the sound effects and the music on SDL's audio thread.
+struct Mixer {
+    /// Samples per second.
+    rate: f32,
+    voices: Vec<Voice>,
+    music: Music,
+    /// Volumes of the effects and of the music, 0 to 1.
+    effects: f32,
+    music_volume: f32,
+    /// State of the generator of the noise.
+    seed: u32,
+    noise: f32,
+}
+
+impl Mixer {
+    /// Returns the next sample of the effects.
+    fn effects(&mut self) -> f32 {
+        let mut sample = 0.0;
+        for voice in &mut self.voices {
+            let Some(tone) = voice.tones.get(voice.tone) else { continue };
+            let length = (tone.length * self.rate) as u32;
+            let progress = voice.played as f32 / length as f32;
+            // a short attack not to click, then a linear fade out
+            let envelope = (1.0 - progress).min(progress * 50.0);
+            sample += tone.wave.at(voice.phase, self.noise) * tone.volume * envelope;
+            voice.phase += (tone.from + (tone.to - tone.from) * progress) / self.rate;
+            if voice.phase >= 1.0 {
+                voice.phase -= 1.0;
+                // a xorshift, for a noise changing at the frequency of the tone
+                self.seed ^= self.seed << 13;
+                self.seed ^= self.seed >> 17;
+                self.seed ^= self.seed << 5;
+                self.noise = self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            }
+            voice.played += 1;
+            if voice.played >= length {
+                voice.tone += 1;
+                voice.played = 0;
+            }
+        }
+        self.voices.retain(|voice| voice.tone < voice.tones.len());
+        sample
+    }
+}
+
+impl AudioCallback for Mixer {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out {
+            let mixed = self.effects() * self.effects + self.music.sample(self.rate) * self.music_volume;
+            *sample = mixed.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Returns the volumes of the effects and of the music of `volume`, from 0 to 1.
+fn volumes(volume: Volume) -> (f32, f32) {
+    let percent = |value: u32| value.min(MAX_VOLUME) as f32 / MAX_VOLUME as f32;
+    (percent(volume.master) * percent(volume.effects), percent(volume.master) * percent(volume.music))
+}
+
+/// The sound of the window: the sound effects and the music, synthesized as they are played.
+pub(crate) struct Audio {
+    device: AudioDevice<Mixer>,
+}
+
+impl Audio {
+    /// Opens the default audio device of `sdl`, playing at `volume`.
+    /// # Returns
+    /// an error if SDL has no audio device (e.g. without sound card), to play without sound.
+    pub(crate) fn open(sdl: &Sdl, volume: Volume) -> Result<Self, String> {
+        let desired = AudioSpecDesired {
+            freq: Some(RATE),
+            channels: Some(1),
+            samples: Some(512),
+        };
+        let device = sdl.audio()?.open_playback(None, &desired, |spec| {
+            let (effects, music_volume) = volumes(volume);
+            Mixer {
+                rate: spec.freq as f32,
+                voices: Vec::new(),
+                music: Music::default(),
+                effects,
+                music_volume,
+                seed: 0x9E37_79B9,
+                noise: 0.0,
+            }
+        })?;
+        device.resume();
+        Ok(Self { device })
+    }
+    /// Plays `sound` over the ones playing.
+    pub(crate) fn play(&mut self, sound: Sound) {
+        let mut mixer = self.device.lock();
+        if mixer.voices.len() >= MAX_VOICES {
+            mixer.voices.remove(0);
+        }
+        mixer.voices.push(Voice { tones: sound.tones(), tone: 0, played: 0, phase: 0.0 });
+    }
+    /// Plays the sounds at `volume`.
+    pub(crate) fn set_volume(&mut self, volume: Volume) {
+        let mut mixer = self.device.lock();
+        (mixer.effects, mixer.music_volume) = volumes(volume);
+    }
+    /// Plays the music at `tempo` times its speed if `playing`, pauses it otherwise.
+    pub(crate) fn set_music(&mut self, playing: bool, tempo: f32) {
+        let mut mixer = self.device.lock();
+        mixer.music.playing = playing;
+        mixer.music.tempo = tempo;
+    }
+}
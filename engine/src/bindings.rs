@@ -0,0 +1,56 @@
+use config::Keys;
+use game::Action;
+use sdl2::{controller::Button, keyboard::Keycode};
+use std::collections::HashMap;
+
+/// The keys bound to each `Action` of a player (none for a remote player).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Bindings(HashMap<Keycode, Action>);
+
+impl Bindings {
+    /// Creates the bindings of the keys named in `keys` (`section` is the table of the config
+    /// they come from).
+    /// # Returns
+    /// an error naming the first key SDL doesn't know.
+    pub(crate) fn new(keys: &Keys, section: &str) -> Result<Self, String> {
+        let mut bindings = HashMap::new();
+        for (action, field, names) in keys.iter() {
+            for name in names {
+                let key = Keycode::from_name(name)
+                    .ok_or_else(|| format!("unknown key {name:?} in bindings.{section}.{field}"))?;
+                bindings.insert(key, action);
+            }
+        }
+        Ok(Self(bindings))
+    }
+    /// Returns the action bound to `key`, `None` if there isn't one.
+    pub(crate) fn action(&self, key: Keycode) -> Option<Action> {
+        self.0.get(&key).copied()
+    }
+}
+
+/// The buttons of a game controller bound to each `Action`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Buttons(HashMap<Button, Action>);
+
+impl Buttons {
+    /// Creates the bindings of the buttons named in `buttons` (`section` is the table of the
+    /// config they come from).
+    /// # Returns
+    /// an error naming the first button SDL doesn't know.
+    pub(crate) fn new(buttons: &Keys, section: &str) -> Result<Self, String> {
+        let mut bindings = HashMap::new();
+        for (action, field, names) in buttons.iter() {
+            for name in names {
+                let button = Button::from_string(name)
+                    .ok_or_else(|| format!("unknown button {name:?} in controllers.{section}.{field}"))?;
+                bindings.insert(button, action);
+            }
+        }
+        Ok(Self(bindings))
+    }
+    /// Returns the action bound to `button`, `None` if there isn't one.
+    pub(crate) fn action(&self, button: Button) -> Option<Action> {
+        self.0.get(&button).copied()
+    }
+}
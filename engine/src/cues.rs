@@ -0,0 +1,102 @@
+use crate::audio::Sound;
+use game::{Game, Outcome};
+use matrix::Matrix;
+use vecmath::Direction;
+
+/// Rows of the highest stack from which the music speeds up.
+const CALM_ROWS: usize = 10;
+/// Times the normal speed the music is played at when a stack reaches the top.
+const MAX_TEMPO: f32 = 1.5;
+
+/// What was last heard of a game, to find what changed since.
+#[derive(Default)]
+struct Heard {
+    frames: u32,
+    pieces: u32,
+    lines: u32,
+    t_spins: u32,
+    combo: Option<u32>,
+    level: u32,
+    outcome: Option<Outcome>,
+    /// Column and direction of the `cur_tetromino`.
+    current: Option<(isize, Direction)>,
+}
+
+/// Returns what is heard of `game` now.
+fn heard(game: &Game) -> Heard {
+    Heard {
+        frames: game.stats.frames,
+        pieces: game.stats.pieces,
+        lines: game.stats.lines,
+        t_spins: game.stats.t_spins,
+        combo: game.combo,
+        level: game.level,
+        outcome: game.outcome,
+        current: game.cur_tetromino.as_ref().map(|tetromino| (tetromino.offset.x, tetromino.direction())),
+    }
+}
+
+/// Finds the sound effects of what happens in the games.
+#[derive(Default)]
+pub(crate) struct Cues {
+    /// What was last heard of each game.
+    heard: Vec<Heard>,
+}
+
+impl Cues {
+    /// Returns the sounds of what changed in `games` since the last call
+    /// (none for a game which started again).
+    pub(crate) fn update<'a>(&mut self, games: impl IntoIterator<Item = &'a Game>) -> Vec<Sound> {
+        let games: Vec<_> = games.into_iter().collect();
+        self.heard.resize_with(games.len(), Default::default);
+        let mut sounds = Vec::new();
+        for (game, before) in games.into_iter().zip(&mut self.heard) {
+            let now = heard(game);
+            if now.frames < before.frames || now.pieces < before.pieces {
+                *before = now;
+                continue;
+            }
+            if now.outcome != before.outcome {
+                match now.outcome {
+                    Some(Outcome::ToppedOut) => sounds.push(Sound::GameOver),
+                    Some(Outcome::Completed) => sounds.push(Sound::Completed),
+                    None => {}
+                }
+            } else if now.pieces > before.pieces {
+                sounds.push(if now.t_spins > before.t_spins {
+                    Sound::TSpin
+                } else if now.lines > before.lines {
+                    Sound::Clear(now.lines - before.lines)
+                } else {
+                    Sound::Lock
+                });
+                if let Some(combo) = now.combo.filter(|&combo| combo > 0 && now.combo != before.combo) {
+                    sounds.push(Sound::Combo(combo));
+                }
+                if now.level > before.level {
+                    sounds.push(Sound::LevelUp);
+                }
+            } else if let (Some((x, direction)), Some((last_x, last_direction))) = (now.current, before.current) {
+                if direction != last_direction {
+                    sounds.push(Sound::Rotate);
+                } else if x != last_x {
+                    sounds.push(Sound::Move);
+                }
+            }
+            *before = now;
+        }
+        sounds
+    }
+}
+
+/// Returns the tempo of the music for `games`: the normal one, rising to `MAX_TEMPO` as the
+/// highest stack grows from `CALM_ROWS` rows to the top.
+pub(crate) fn tempo<'a>(games: impl IntoIterator<Item = &'a Game>) -> f32 {
+    let height = games
+        .into_iter()
+        .map(|game| Matrix::HEIGHT - game.matrix.iter().position(|row| row.iter().any(Option::is_some)).unwrap_or(Matrix::HEIGHT))
+        .max()
+        .unwrap_or(0);
+    let danger = height.saturating_sub(CALM_ROWS) as f32 / (Matrix::HEIGHT - CALM_ROWS) as f32;
+    1.0 + (MAX_TEMPO - 1.0) * danger
+}
@@ -1,25 +1,31 @@
-use game::Game;
-use matrix::Matrix;
+use crate::{
+    records::Record,
+    skin::Blocks,
+    text::Texts,
+};
+use config::{Config, theme::GridStyle};
+use game::{Game, Mode, Outcome};
+use matrix::{Block, Links, Matrix};
+use pieces::{Rgb, Tetromino, TetrominoKind};
 use vecmath::Point;
-use std::error::Error as StdError;
+use std::{
+    error::Error as StdError,
+    time::Duration,
+};
 use sdl2::{
     pixels::Color,
     rect::{
         Rect,
         Point as SdlPoint,
     },
-    render::{
-        Canvas,
-        TextureQuery
-    },
-    ttf::Sdl2TtfContext,
-    video::Window,
+    render::{Canvas, RenderTarget},
 };
 
 const CELL_SIZE: u32 = 52;
 const DISPLAY_SIZE: u32 = 360;
+/// Size of the cells of the next pieces after the first one.
+const SMALL_CELL_SIZE: u32 = CELL_SIZE / 4;
 const MARGIN: u32 = 20;
-const WALLPAPER_COLOR: Color = Color::RGB(50, 50, 50);
 const MATRIX_WIDTH: u32 = Matrix::WIDTH as u32 * CELL_SIZE;
 const MATRIX_HEIGHT: u32 = Matrix::HEIGHT as u32 * CELL_SIZE;
 
@@ -30,10 +36,37 @@ pub(crate) const WIDTH: u32 = MATRIX_WIDTH
 pub(crate) const HEIGHT: u32 = MATRIX_HEIGHT
     + MARGIN * 2
     - 1;
+/// Frames the matrix flashes for after a fault in the finesse trainer.
+const FAULT_FLASH: u32 = 20;
+/// Width of the column of the live stats, right of the panel.
+const STATS_WIDTH: u32 = DISPLAY_SIZE;
+/// Longest bar of the piece distribution (the one of the most placed kind).
+const BAR_WIDTH: u32 = STATS_WIDTH - MARGIN * 3 - SMALL_CELL_SIZE * 4 - 100;
 
-pub(crate) fn draw(canvas: &mut Canvas<Window>, ttf: &Sdl2TtfContext, game: &mut Game) -> Result<(), Box<dyn StdError>> {
-    // 3 rectangles color
-    canvas.set_draw_color(WALLPAPER_COLOR);
+/// Returns the width of the layout of a player, with the column of the live stats if `stats`.
+pub(crate) fn width(stats: bool) -> u32 {
+    if stats { WIDTH + STATS_WIDTH + MARGIN } else { WIDTH }
+}
+
+/// Fits the layout of `width` (and `HEIGHT`) into an output of `size` pixels, keeping its
+/// proportions.
+/// # Returns
+/// the scale of the layout and its area in the scaled coordinates, centered between black bars.
+pub(crate) fn fit(size: (u32, u32), width: u32) -> (f32, Rect) {
+    let (output_width, output_height) = (size.0.max(1) as f32, size.1.max(1) as f32);
+    let scale = (output_width / width as f32).min(output_height / HEIGHT as f32);
+    let area = Rect::new(
+        ((output_width / scale - width as f32) / 2.0) as i32,
+        ((output_height / scale - HEIGHT as f32) / 2.0) as i32,
+        width,
+        HEIGHT,
+    );
+    (scale, area)
+}
+
+pub(crate) fn draw<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, game: &mut Game, pb: Option<&Record>, config: &Config, blocks: &Blocks) -> Result<(), Box<dyn StdError>> {
+    let theme = blocks.theme;
+    canvas.set_draw_color(rgb(theme.background.matrix));
 
     // matrix background
     canvas.fill_rect(Rect::new(
@@ -44,6 +77,7 @@ pub(crate) fn draw(canvas: &mut Canvas<Window>, ttf: &Sdl2TtfContext, game: &mut
     ))?;
 
     // score background
+    canvas.set_draw_color(rgb(theme.background.panels));
     canvas.fill_rect(Rect::new(
         MARGIN as i32 * 2 + MATRIX_WIDTH as i32,
         MARGIN as i32 + CELL_SIZE as i32 * 3 / 2,
@@ -59,65 +93,133 @@ pub(crate) fn draw(canvas: &mut Canvas<Window>, ttf: &Sdl2TtfContext, game: &mut
         DISPLAY_SIZE
     ))?;
 
-    let color = game.cur_tetromino.as_ref().unwrap().get_color();
-    // ghost blocks
-    canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, 40));
-    for coord in game.get_ghost().unwrap().position() {
-        canvas.fill_rect(draw_block(coord))?;
+    // target of the finesse trainer, outlined
+    if let Some(target) = &game.target {
+        canvas.set_draw_color(rgb(blocks.colors.piece(target.kind())));
+        for coord in target.position() {
+            let rect = draw_block(coord);
+            canvas.draw_rect(rect)?;
+            canvas.draw_rect(Rect::new(rect.x() + 1, rect.y() + 1, rect.width() - 2, rect.height() - 2))?;
+        }
     }
 
-    // cur_tetromino blocks
-    canvas.set_draw_color(color);
-    for coord in game.cur_tetromino.as_ref().unwrap().position() {
-        canvas.fill_rect(draw_block(coord))?;
+    if let Some(cur) = &game.cur_tetromino {
+        // ghost blocks
+        if config.ghost {
+            let position = game.get_ghost().unwrap().position();
+            for coord in position {
+                blocks.draw_ghost(canvas, draw_block(coord), Block::Piece(cur.kind(), Links::between(coord, &position)))?;
+            }
+        }
+
+        // cur_tetromino blocks
+        let position = cur.position();
+        for coord in position {
+            blocks.draw(canvas, draw_block(coord), Block::Piece(cur.kind(), Links::between(coord, &position)))?;
+        }
     }
 
-    // next_tetromino blocks
-    if let Some(next) = game.bag.last() {
-        canvas.set_draw_color(next.get_color());
+    // next_tetromino blocks (the first one big, the others small in a row under it)
+    if game.bag.is_empty() {
+        game.refill_bag();
+    }
+    for (i, next) in game.bag.iter().rev().take(config.preview).enumerate() {
         for coord in next.cells {
-            canvas.fill_rect(Rect::new(
-                MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 3 + MARGIN as i32 + coord.x as i32 * CELL_SIZE as i32,
-                MARGIN as i32 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32 / 3 + MARGIN as i32 + coord.y as i32 * CELL_SIZE as i32,
-                CELL_SIZE,
-                CELL_SIZE
-            ))?;
+            let rect = if i == 0 {
+                Rect::new(
+                    MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 3 + MARGIN as i32 + coord.x as i32 * CELL_SIZE as i32,
+                    MARGIN as i32 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32 / 3 + MARGIN as i32 + coord.y as i32 * CELL_SIZE as i32,
+                    CELL_SIZE,
+                    CELL_SIZE
+                )
+            } else {
+                Rect::new(
+                    MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + MARGIN as i32 / 2 + (i as i32 - 1) * SMALL_CELL_SIZE as i32 * 5 + coord.x as i32 * SMALL_CELL_SIZE as i32,
+                    MARGIN as i32 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32 - MARGIN as i32 - SMALL_CELL_SIZE as i32 * 2 + coord.y as i32 * SMALL_CELL_SIZE as i32,
+                    SMALL_CELL_SIZE,
+                    SMALL_CELL_SIZE
+                )
+            };
+            blocks.draw(canvas, rect, Block::Piece(next.kind(), Links::between(coord, &next.cells)))?;
         }
-    } else {
-        game.refill_bag();
     }
 
-    // matrix blocks
+    // matrix blocks (the cleared rows are animated)
     for (y, row) in game.matrix.iter().enumerate() {
+        if game.clearing.contains(&y) {
+            draw_clearing(canvas, game, y, blocks)?;
+            continue;
+        }
         for (x, block) in row.iter().enumerate() {
-            let Some(color) = block else { continue };
-            canvas.set_draw_color(*color);
-            canvas.fill_rect(Rect::new(
+            let Some(block) = block else { continue };
+            blocks.draw(canvas, Rect::new(
                 MARGIN as i32 + x as i32 * CELL_SIZE as i32,
                 MARGIN as i32 + y as i32 * CELL_SIZE as i32,
                 CELL_SIZE,
                 CELL_SIZE,
-            ))?;
+            ), *block)?;
         }
     }
 
-    // # Matrix grid
-    canvas.set_draw_color(Color::BLACK);
-    // horizontal
-    for i in 0..=Matrix::HEIGHT {
-        let y = (i as u32 * CELL_SIZE) as i32 + (MARGIN as i32);
-        canvas.draw_line(
-            SdlPoint::new(MARGIN as i32, y),
-            SdlPoint::new(MATRIX_WIDTH as i32 + MARGIN as i32 * 2, y)
-        )?;
+    // flash after a fault of the finesse trainer, fading out
+    if let Some(frame) = game.last_fault && game.stats.frames - frame < FAULT_FLASH {
+        let (r, g, b) = theme.hud.incoming;
+        let alpha = 120 * (FAULT_FLASH - (game.stats.frames - frame)) / FAULT_FLASH;
+        canvas.set_draw_color(Color::RGBA(r, g, b, alpha as u8));
+        canvas.fill_rect(Rect::new(
+            MARGIN as i32,
+            MARGIN as i32,
+            MATRIX_WIDTH,
+            MATRIX_HEIGHT,
+        ))?;
     }
-    // vertical
-    for i in 0..=Matrix::WIDTH {
-        let x = (i as u32 * CELL_SIZE) as i32 + (MARGIN as i32);
-        canvas.draw_line(
-            SdlPoint::new(x, MARGIN as i32 - CELL_SIZE as i32),
-            SdlPoint::new(x, HEIGHT as i32)
-        )?;
+
+    // incoming garbage, in the margin left of the matrix
+    let incoming = (game.incoming.iter().sum::<u32>() as usize).min(Matrix::HEIGHT) as u32;
+    if incoming > 0 {
+        canvas.set_draw_color(rgb(theme.hud.incoming));
+        canvas.fill_rect(Rect::new(
+            MARGIN as i32 / 4,
+            MARGIN as i32 + MATRIX_HEIGHT as i32 - incoming as i32 * CELL_SIZE as i32,
+            MARGIN / 2,
+            incoming * CELL_SIZE,
+        ))?;
+    }
+
+    // # Matrix grid
+    if config.grid {
+        canvas.set_draw_color(rgb(theme.grid.color));
+        match theme.grid.style {
+            GridStyle::Lines => {
+                // horizontal
+                for i in 0..=Matrix::HEIGHT {
+                    let y = (i as u32 * CELL_SIZE) as i32 + (MARGIN as i32);
+                    canvas.draw_line(
+                        SdlPoint::new(MARGIN as i32, y),
+                        SdlPoint::new(MATRIX_WIDTH as i32 + MARGIN as i32 * 2, y)
+                    )?;
+                }
+                // vertical
+                for i in 0..=Matrix::WIDTH {
+                    let x = (i as u32 * CELL_SIZE) as i32 + (MARGIN as i32);
+                    canvas.draw_line(
+                        SdlPoint::new(x, MARGIN as i32 - CELL_SIZE as i32),
+                        SdlPoint::new(x, HEIGHT as i32)
+                    )?;
+                }
+            }
+            GridStyle::Dots => {
+                let dots: Vec<_> = (0..=Matrix::HEIGHT)
+                    .flat_map(|y| (0..=Matrix::WIDTH).map(move |x| Rect::new(
+                        MARGIN as i32 + (x as u32 * CELL_SIZE) as i32 - 2,
+                        MARGIN as i32 + (y as u32 * CELL_SIZE) as i32 - 2,
+                        4,
+                        4,
+                    )))
+                    .collect();
+                canvas.fill_rects(&dots)?;
+            }
+        }
     }
 
     // # text
@@ -125,61 +227,420 @@ pub(crate) fn draw(canvas: &mut Canvas<Window>, ttf: &Sdl2TtfContext, game: &mut
     // "NEXT"
     text(
         canvas,
-        ttf,
+        texts,
         MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7 * 2,
         MARGIN as i32 + CELL_SIZE as i32 * 8,
-        "NEXT",
-        "/System/Library/Fonts/Supplemental/Arial.ttf"
+        "NEXT"
     )?;
 
     // "SCORE"
     text(
         canvas,
-        ttf,
+        texts,
         MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 4,
         MARGIN as i32 * 3 / 2,
-        "SCORE",
-        "/System/Library/Fonts/Supplemental/Arial.ttf"
+        "SCORE"
     )?;
 
     // "{score}"
     text(
         canvas,
-        ttf,
+        texts,
         MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 2,
         MARGIN as i32 + CELL_SIZE as i32 * 2,
-        game.score.to_string().as_str(),
-        "/System/Library/Fonts/Supplemental/Arial.ttf"
+        game.score.to_string().as_str()
     )?;
 
+    if let Some(limit) = game.mode.time_limit() {
+        // "{time left}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 4,
+            &format_time(limit.saturating_sub(game.stats.time()))
+        )?;
+
+        // "PPM {points per minute}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 11 / 2,
+            &format!("PPM {:.0}", game.points_per_minute())
+        )?;
+    }
+
+    let splits_pb = match pb {
+        Some(Record::Splits(splits)) => Some(splits.as_slice()),
+        _ => None,
+    };
+
+    if let Mode::Marathon { endless, .. } = game.mode {
+        // "LEVEL {level}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 11 / 2,
+            &format!("LEVEL {}", game.level)
+        )?;
+
+        // "LINES {lines}" (the remaining ones are shown if the marathon has an end)
+        if endless {
+            text(
+                canvas,
+                texts,
+                MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+                MARGIN as i32 * 2 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32,
+                &format!("LINES {}", game.stats.lines)
+            )?;
+        }
+    }
+
+    if let Mode::Dig { .. } = game.mode {
+        // "{time}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 4,
+            &format_time(game.stats.time())
+        )?;
+
+        // "GARBAGE {remaining}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 * 2 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32,
+            &format!("GARBAGE {}", game.matrix.garbage_rows())
+        )?;
+    }
+
+    if game.mode == Mode::Finesse {
+        // "{time}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 4,
+            &format_time(game.stats.time())
+        )?;
+
+        // "RETRIES {retries}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 11 / 2,
+            &format!("RETRIES {}", game.stats.retries)
+        )?;
+
+        // "LEFT {pieces to place}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 * 2 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32,
+            &format!("LEFT {}", Mode::FINESSE_PIECES.saturating_sub(game.stats.pieces - game.stats.retries))
+        )?;
+    }
+
+    if let Some(goal) = game.mode.goal() {
+        // "{time}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 + CELL_SIZE as i32 * 4,
+            &format_time(game.stats.time())
+        )?;
+
+        // last split compared with the personal best
+        let i = game.stats.splits.len();
+        if let (Some(split), Some(best)) = (game.stats.splits.last(), splits_pb.and_then(|pb| pb.get(i.wrapping_sub(1)))) {
+            text(
+                canvas,
+                texts,
+                MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+                MARGIN as i32 + CELL_SIZE as i32 * 11 / 2,
+                &format!("{} {}", i as u32 * Mode::SPLIT_LINES, format_delta(*split, *best))
+            )?;
+        }
+
+        // "LINES {remaining}"
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32 / 7,
+            MARGIN as i32 * 2 + CELL_SIZE as i32 * 19 / 2 + DISPLAY_SIZE as i32,
+            &format!("LINES {}", goal.saturating_sub(game.stats.lines))
+        )?;
+    }
+
+    if config.stats {
+        draw_stats(canvas, texts, game, blocks)?;
+    }
+
     Ok(())
 }
 
-fn draw_block(coord: Point) -> Rect {
-    Rect::new(
-        MARGIN as i32 + coord.x as i32 * CELL_SIZE as i32,
-        MARGIN as i32 + coord.y as i32 * CELL_SIZE as i32,
-        CELL_SIZE,
-        CELL_SIZE,
-    )
+/// Draws the live stats of `game` in the column right of its panel: its time, its pieces and its
+/// lines with their rates, its finesse faults and how many pieces of each kind it placed.
+fn draw_stats<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, game: &Game, blocks: &Blocks) -> Result<(), Box<dyn StdError>> {
+    let left = MARGIN as i32 * 3 + MATRIX_WIDTH as i32 + DISPLAY_SIZE as i32;
+    let row = CELL_SIZE as i32 * 6 / 5;
+    canvas.set_draw_color(rgb(blocks.theme.background.panels));
+    canvas.fill_rect(Rect::new(
+        left,
+        MARGIN as i32,
+        STATS_WIDTH,
+        MATRIX_HEIGHT,
+    ))?;
+
+    let stats = &game.stats;
+    let lines = [
+        format_time(stats.time()),
+        format!("PIECES {}", stats.pieces),
+        format!("PPS {:.2}", stats.pps()),
+        format!("KPP {:.2}", stats.kpp()),
+        format!("LINES {}", stats.lines),
+        format!("LPM {:.1}", stats.lpm()),
+        format!("APM {:.1}", stats.apm()),
+        format!("FINESSE {}", stats.finesse_faults),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        text(canvas, texts, left + MARGIN as i32, MARGIN as i32 + i as i32 * row, line)?;
+    }
+
+    // piece distribution: each kind, a bar as long as its share of the most placed one
+    // and its count
+    let top = MARGIN as i32 * 2 + lines.len() as i32 * row;
+    let most = TetrominoKind::ALL.iter().map(|&kind| stats.placed(kind)).max().unwrap_or(0).max(1);
+    for (i, kind) in TetrominoKind::ALL.into_iter().enumerate() {
+        let y = top + i as i32 * row;
+        let cells = Tetromino::new(kind).cells;
+        for coord in cells {
+            blocks.draw(canvas, Rect::new(
+                left + MARGIN as i32 + coord.x as i32 * SMALL_CELL_SIZE as i32,
+                y + row / 2 - SMALL_CELL_SIZE as i32 + coord.y as i32 * SMALL_CELL_SIZE as i32,
+                SMALL_CELL_SIZE,
+                SMALL_CELL_SIZE
+            ), Block::Piece(kind, Links::between(coord, &cells)))?;
+        }
+        let placed = stats.placed(kind);
+        let bar = BAR_WIDTH * placed / most;
+        if bar > 0 {
+            canvas.set_draw_color(rgb(blocks.colors.piece(kind)));
+            canvas.fill_rect(Rect::new(
+                left + MARGIN as i32 * 2 + SMALL_CELL_SIZE as i32 * 4,
+                y + row / 4,
+                bar,
+                row as u32 / 2
+            ))?;
+        }
+        let count = placed.to_string();
+        text(canvas, texts, left + STATS_WIDTH as i32 - MARGIN as i32 - texts.width(&count)? as i32, y, &count)?;
+    }
+
+    Ok(())
 }
 
-fn text(canvas: &mut Canvas<Window>, ttf: &Sdl2TtfContext, x: i32, y: i32, text: &str, font_path: &str) -> Result<(), Box<dyn StdError>> {
-    let texture_creator = canvas.texture_creator();
+/// Draws the results of an ended game over the matrix, with the keys to play again if it can `retry`.
+pub(crate) fn draw_results<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, game: &Game, pb: Option<&Record>, new_pb: bool, retry: bool) -> Result<(), Box<dyn StdError>> {
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+    canvas.fill_rect(Rect::new(
+        MARGIN as i32,
+        MARGIN as i32,
+        MATRIX_WIDTH,
+        MATRIX_HEIGHT,
+    ))?;
+
+    let title = match game.outcome {
+        _ if new_pb => "NEW BEST!",
+        Some(Outcome::Completed) if game.mode == Mode::Versus => "YOU WIN",
+        Some(Outcome::ToppedOut) if game.mode == Mode::Versus => "YOU LOSE",
+        Some(Outcome::Completed) => "FINISHED",
+        _ => "GAME OVER",
+    };
+    let mut lines = vec![
+        title.to_string(),
+        String::new(),
+        format!("SCORE {}", game.score),
+        format!("TIME {}", format_time(game.stats.time())),
+        format!("PPM {:.0}", game.points_per_minute()),
+        format!("PPS {:.2}", game.stats.pps()),
+        format!("KPP {:.2}", game.stats.kpp()),
+        format!("FINESSE {}", game.stats.finesse_faults),
+        String::new(),
+    ];
+    match (game.mode, pb) {
+        (Mode::Sprint, pb) => {
+            for (i, split) in game.stats.splits.iter().enumerate() {
+                let delta = match pb {
+                    Some(Record::Splits(pb)) if i < pb.len() => format_delta(*split, pb[i]),
+                    _ => String::new(),
+                };
+                lines.push(format!("{} {} {}", (i as u32 + 1) * Mode::SPLIT_LINES, format_time(*split), delta));
+            }
+        }
+        (Mode::Marathon { .. }, Some(Record::Score(best))) => {
+            lines.push(format!("LEVEL {} LINES {}", game.level, game.stats.lines));
+            lines.push(format!("BEST {}", best));
+        }
+        (Mode::Marathon { .. }, _) => {
+            lines.push(format!("LEVEL {} LINES {}", game.level, game.stats.lines));
+        }
+        (Mode::Finesse, _) => {
+            lines.push(format!("PIECES {} RETRIES {}", game.stats.pieces, game.stats.retries));
+        }
+        (Mode::Versus, _) => {
+            lines.push(format!("LINES {} ATTACK {}", game.stats.lines, game.stats.attack));
+        }
+        _ => {}
+    }
+    lines.push(String::new());
+    if retry {
+        lines.push("ENTER: RETRY".to_string());
+    }
+    lines.push("ESC: MENU".to_string());
 
-    let font = ttf.load_font(font_path, 50)?;
+    for (i, line) in lines.iter().enumerate().filter(|(_, line)| !line.is_empty()) {
+        text(
+            canvas,
+            texts,
+            MARGIN as i32 * 2,
+            MARGIN as i32 + CELL_SIZE as i32 + i as i32 * CELL_SIZE as i32 * 6 / 5,
+            line
+        )?;
+    }
 
-    let surface = font
-        .render(text)
-        .blended(Color::WHITE)?;
+    Ok(())
+}
 
-    let texture = texture_creator.create_texture_from_surface(&surface)?;
+/// A menu of rows with a label and a value.
+pub(crate) struct Menu<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) rows: Vec<(String, String)>,
+    pub(crate) selected: usize,
+    /// Line under the rows (the keys to use).
+    pub(crate) hint: String,
+}
 
-    let TextureQuery { width, height, .. } = texture.query();
+/// Draws `menu` over the window of `width`, scrolled to its selected row if it doesn't fit.
+pub(crate) fn draw_menu<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, width: u32, menu: &Menu) -> Result<(), Box<dyn StdError>> {
+    let row_height = CELL_SIZE as i32 * 6 / 5;
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 230));
+    canvas.fill_rect(Rect::new(0, 0, width, HEIGHT))?;
 
-    let target = Rect::new(x, y, width, height);
+    text(canvas, texts, MARGIN as i32 * 2, MARGIN as i32, menu.title)?;
 
-    canvas.copy(&texture, None, Some(target))?;
+    // rows between the title and the hint
+    let visible = (HEIGHT as i32 - MARGIN as i32 * 2) as usize / row_height as usize - 3;
+    let first = menu.selected.saturating_sub(visible - 1);
+    for (i, (label, value)) in menu.rows.iter().enumerate().skip(first).take(visible) {
+        let y = MARGIN as i32 + row_height * ((i - first) as i32 + 2);
+        if i == menu.selected {
+            text(canvas, texts, MARGIN as i32, y, ">")?;
+        }
+        text(canvas, texts, MARGIN as i32 * 3, y, label)?;
+        let value_width = texts.width(value)?;
+        text(canvas, texts, width as i32 - MARGIN as i32 * 2 - value_width as i32, y, value)?;
+    }
 
+    text(canvas, texts, MARGIN as i32 * 2, MARGIN as i32 + row_height * (visible as i32 + 2), &menu.hint)?;
     Ok(())
 }
+
+/// Draws `banner` at the middle of the matrix of the player `i`, whose layout is `width` wide.
+pub(crate) fn draw_banner<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, i: usize, width: u32, banner: &str) -> Result<(), Box<dyn StdError>> {
+    let left = i as i32 * width as i32 + MARGIN as i32;
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+    canvas.fill_rect(Rect::new(
+        left,
+        MARGIN as i32 + MATRIX_HEIGHT as i32 / 2 - CELL_SIZE as i32,
+        MATRIX_WIDTH,
+        CELL_SIZE * 2,
+    ))?;
+    text(
+        canvas,
+        texts,
+        left + (MATRIX_WIDTH - texts.width(banner)?) as i32 / 2,
+        MARGIN as i32 + MATRIX_HEIGHT as i32 / 2 - CELL_SIZE as i32 / 2,
+        banner
+    )
+}
+
+/// Draws `toast` at the bottom left of the layout, over the scenes.
+pub(crate) fn draw_toast<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, toast: &str) -> Result<(), Box<dyn StdError>> {
+    let top = (HEIGHT - MARGIN - CELL_SIZE) as i32;
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+    canvas.fill_rect(Rect::new(MARGIN as i32, top, texts.width(toast)? + MARGIN * 2, CELL_SIZE))?;
+    text(canvas, texts, MARGIN as i32 * 2, top + CELL_SIZE as i32 / 4, toast)
+}
+
+/// Draws the row `y` of the matrix, cleared during the line clear delay: it flashes white
+/// for the first half of the delay, then dissolves from the middle outwards.
+fn draw_clearing(canvas: &mut Canvas<impl RenderTarget>, game: &Game, y: usize, blocks: &Blocks) -> Result<(), String> {
+    let total = game.delays.line_clear.max(1);
+    let elapsed = total - game.delay.min(total);
+    let half = total / 2;
+    for (x, block) in game.matrix[y].iter().enumerate() {
+        let Some(block) = block else { continue };
+        let rect = Rect::new(
+            MARGIN as i32 + x as i32 * CELL_SIZE as i32,
+            MARGIN as i32 + y as i32 * CELL_SIZE as i32,
+            CELL_SIZE,
+            CELL_SIZE,
+        );
+        if elapsed < half {
+            blocks.draw(canvas, rect, *block)?;
+            // on and off every 4 frames
+            let alpha = if (elapsed / 4).is_multiple_of(2) { 200 } else { 60 };
+            canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha));
+            canvas.fill_rect(rect)?;
+            continue;
+        }
+        let progress = (elapsed - half) as f32 / (total - half) as f32;
+        // distance of the cell from the middle of the row, from 0 to 1
+        let distance = (x as f32 + 0.5 - Matrix::WIDTH as f32 / 2.0).abs() / (Matrix::WIDTH as f32 / 2.0);
+        if distance < progress {
+            continue;
+        }
+        let alpha = (255.0 * (1.0 - progress)) as u8;
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha));
+        canvas.fill_rect(rect)?;
+    }
+    Ok(())
+}
+
+/// Converts a color of the theme into an SDL one.
+fn rgb((r, g, b): Rgb) -> Color {
+    Color::RGB(r, g, b)
+}
+
+/// Formats `time` as `m:ss.mmm`.
+pub(crate) fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
+
+/// Formats the difference between `time` and `best` as `+s.mmm` or `-s.mmm`.
+fn format_delta(time: Duration, best: Duration) -> String {
+    let (sign, delta) = if time >= best { ('+', time - best) } else { ('-', best - time) };
+    format!("{}{}.{:03}", sign, delta.as_secs(), delta.subsec_millis())
+}
+
+fn draw_block(coord: Point) -> Rect {
+    Rect::new(
+        MARGIN as i32 + coord.x as i32 * CELL_SIZE as i32,
+        MARGIN as i32 + coord.y as i32 * CELL_SIZE as i32,
+        CELL_SIZE,
+        CELL_SIZE,
+    )
+}
+
+pub(crate) fn text<T: RenderTarget>(canvas: &mut Canvas<T>, texts: &mut Texts<T::Context>, x: i32, y: i32, text: &str) -> Result<(), Box<dyn StdError>> {
+    texts.draw(canvas, x, y, text)
+}
@@ -0,0 +1,199 @@
+use crate::{interface, records};
+use game::{Delays, Game, Mode, Outcome};
+use std::{
+    cmp::Reverse,
+    fs,
+    io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Entries kept on each leaderboard.
+pub(crate) const TOP: usize = 10;
+/// Longest name of a player.
+pub(crate) const MAX_NAME: usize = 12;
+/// Name of an entry whose player didn't type one.
+const DEFAULT_NAME: &str = "PLAYER";
+/// Name of the file of the leaderboards, in the data directory.
+const FILE: &str = "leaderboards.txt";
+
+/// A result on a leaderboard.
+#[derive(Clone)]
+pub(crate) struct Entry {
+    pub(crate) mode: Mode,
+    /// Delays of the game: each ruleset has its own leaderboard.
+    pub(crate) delays: Delays,
+    pub(crate) score: u32,
+    /// Frames the game lasted.
+    pub(crate) frames: u32,
+    pub(crate) lines: u32,
+    /// Seconds since the Unix epoch when it was played.
+    pub(crate) date: u64,
+    pub(crate) name: String,
+    pub(crate) seed: u64,
+    /// Replay of the game, `None` if it wasn't saved.
+    pub(crate) replay: Option<PathBuf>,
+}
+
+impl Entry {
+    /// Returns the result of the ended `game` (whose replay was saved at `replay`),
+    /// `None` if it can't be on a leaderboard: a versus, a timed mode which wasn't completed
+    /// or a score of 0.
+    pub(crate) fn new(game: &Game, replay: Option<PathBuf>) -> Option<Self> {
+        let eligible = match game.mode {
+            Mode::Versus => false,
+            mode if by_time(mode) => game.outcome == Some(Outcome::Completed),
+            _ => game.score > 0,
+        };
+        eligible.then(|| Self {
+            mode: game.mode,
+            delays: game.delays,
+            score: game.score,
+            frames: game.stats.frames,
+            lines: game.stats.lines,
+            date: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            name: String::new(),
+            seed: game.seed,
+            replay,
+        })
+    }
+    /// Returns `true` if `self` is on the leaderboard of `mode` and `delays`.
+    fn is_on(&self, mode: Mode, delays: Delays) -> bool {
+        self.mode == mode && self.delays == delays
+    }
+    /// Returns the result the entry is ranked by: its time or its score.
+    pub(crate) fn result(&self) -> String {
+        if by_time(self.mode) {
+            interface::format_time(Game::FRAME * self.frames)
+        } else {
+            self.score.to_string()
+        }
+    }
+    /// Returns the key the entries are sorted by, best first (the oldest first between equals).
+    fn rank_key(&self) -> (u32, Reverse<u32>, u64) {
+        if by_time(self.mode) {
+            (self.frames, Reverse(self.score), self.date)
+        } else {
+            (u32::MAX - self.score, Reverse(self.lines), self.date)
+        }
+    }
+}
+
+/// Returns `true` if the leaderboards of `mode` rank the fastest games, else the highest scores.
+fn by_time(mode: Mode) -> bool {
+    matches!(mode, Mode::Sprint | Mode::Dig { .. } | Mode::Finesse)
+}
+
+/// Returns the name of the leaderboard of `mode` and `delays`: the mode, followed by the delays
+/// if they aren't the default ones (e.g. `SPRINT ARE 10 CLEAR 20`).
+pub(crate) fn title(mode: Mode, delays: Delays) -> String {
+    let mode = mode.to_string().to_uppercase();
+    if delays == Delays::default() {
+        mode
+    } else {
+        format!("{mode} ARE {} CLEAR {}", delays.are, delays.line_clear)
+    }
+}
+
+/// Returns the path of the file of the leaderboards.
+fn path() -> Option<PathBuf> {
+    Some(records::data_dir()?.join(FILE))
+}
+
+/// Loads the entries of every leaderboard, each one sorted best first
+/// (the lines which can't be read are skipped).
+pub(crate) fn load() -> Vec<Entry> {
+    let Some(text) = path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<_> = text.lines().filter_map(parse).collect();
+    entries.sort_by_key(Entry::rank_key);
+    entries
+}
+
+/// Returns the leaderboards which have entries, by mode then by delays.
+pub(crate) fn boards(entries: &[Entry]) -> Vec<(Mode, Delays)> {
+    let mut boards: Vec<_> = entries.iter().map(|entry| (entry.mode, entry.delays)).collect();
+    boards.sort_by_key(|(mode, delays)| (mode.to_string(), delays.are, delays.line_clear));
+    boards.dedup();
+    boards
+}
+
+/// Returns the entries of the leaderboard of `mode` and `delays`, best first.
+pub(crate) fn board(entries: &[Entry], mode: Mode, delays: Delays) -> Vec<Entry> {
+    entries.iter().filter(|entry| entry.is_on(mode, delays)).take(TOP).cloned().collect()
+}
+
+/// Returns the rank `entry` would have on its leaderboard (0 for the first),
+/// `None` if it wouldn't make it.
+pub(crate) fn rank(entries: &[Entry], entry: &Entry) -> Option<usize> {
+    let rank = board(entries, entry.mode, entry.delays)
+        .iter()
+        .take_while(|other| other.rank_key() <= entry.rank_key())
+        .count();
+    (rank < TOP).then_some(rank)
+}
+
+/// Returns the name of the last entry saved, to type the next one from.
+pub(crate) fn last_name(entries: &[Entry]) -> String {
+    entries.iter().max_by_key(|entry| entry.date).map_or_else(String::new, |entry| entry.name.clone())
+}
+
+/// Adds `entry` to its leaderboard, named `DEFAULT_NAME` if its name is empty, and keeps the
+/// best `TOP` entries of the leaderboard.
+/// # Returns
+/// every entry after the change, to show them.
+pub(crate) fn save(mut entry: Entry) -> io::Result<Vec<Entry>> {
+    let Some(path) = path() else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory"));
+    };
+    entry.name = entry.name.trim().to_string();
+    if entry.name.is_empty() {
+        entry.name = DEFAULT_NAME.to_string();
+    }
+    let mut entries = load();
+    entries.push(entry);
+    entries.sort_by_key(Entry::rank_key);
+    // the entries past the top of their leaderboard are dropped
+    let mut kept: Vec<Entry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if kept.iter().filter(|other| other.is_on(entry.mode, entry.delays)).count() < TOP {
+            kept.push(entry);
+        }
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, kept.iter().map(format).collect::<String>())?;
+    Ok(kept)
+}
+
+/// Writes `entry` as a line of tab separated values:
+/// `mode  are line_clear  score  frames  lines  date  seed  name  replay`.
+fn format(entry: &Entry) -> String {
+    let replay = entry.replay.as_ref().map_or_else(String::new, |path| path.to_string_lossy().into_owned());
+    format!(
+        "{}\t{} {}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        entry.mode, entry.delays.are, entry.delays.line_clear, entry.score, entry.frames, entry.lines,
+        entry.date, entry.seed, entry.name, replay,
+    )
+}
+
+/// Reads a line written by `format`.
+fn parse(line: &str) -> Option<Entry> {
+    let [mode, delays, score, frames, lines, date, seed, name, replay] = line.split('\t').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let (are, line_clear) = delays.split_once(' ')?;
+    Some(Entry {
+        mode: mode.parse().ok()?,
+        delays: Delays { are: are.parse().ok()?, line_clear: line_clear.parse().ok()? },
+        score: score.parse().ok()?,
+        frames: frames.parse().ok()?,
+        lines: lines.parse().ok()?,
+        date: date.parse().ok()?,
+        name: name.to_string(),
+        seed: seed.parse().ok()?,
+        replay: Some(PathBuf::from(replay)).filter(|_| !replay.is_empty()),
+    })
+}
@@ -1,18 +1,62 @@
 //! # Engine
 //! Contains the struct which runs the program
 
+/// Synthesize and mix the sound effects and the music
+mod audio;
+/// Map the keys to the players' actions
+mod bindings;
+/// Find the sound effects of the games
+mod cues;
 /// Visualize the program
 mod interface;
+/// Save and load the best results of each mode
+mod leaderboards;
+/// The music of the games
+mod music;
+/// Play with game controllers
+mod pads;
+/// Save and load the personal bests
+mod records;
+/// Save and list the replays
+mod replays;
+/// The screens of the program
+mod scene;
+/// Draw the games without a window and save them as images
+pub mod screenshot;
+/// Change the config from the window
+mod settings;
+/// Draw the blocks
+mod skin;
+/// Stream the events of the games to spectators
+mod spectator;
+/// Render and cache the text
+mod text;
 
-use game::Game;
+use audio::Audio;
+use bindings::Bindings;
+use config::{Config, Theme};
+use cues::Cues;
+use game::{Action, Game, Mode, Replay};
+use leaderboards::Entry;
+use net::Session;
+use pads::Pads;
+use records::Record;
+use scene::Scene;
+use skin::{Atlas, Blocks, Skin};
+use spectator::Spectators;
+use text::Texts;
 use std::{
     error::Error as StdError,
+    io,
+    net::ToSocketAddrs,
     time::{
         Instant,
         Duration
     },
 };
 use sdl2::{
+    clipboard::ClipboardUtil,
+    controller::{Axis, Button},
     event::Event::{
         self,
         KeyDown,
@@ -20,28 +64,474 @@ use sdl2::{
     },
     keyboard::Keycode,
     pixels::Color,
+    rect::Rect,
+    render::Canvas,
+    video::{FullscreenType, Window},
 };
 
-pub struct Engine {
+/// A game with the keys its player plays with.
+struct Player {
     game: Game,
+    bindings: Bindings,
+}
+
+/// Time a toast is shown.
+const TOAST_TIME: Duration = Duration::from_secs(3);
+/// Most time simulated at once: after a stall (e.g. the window dragged or the machine
+/// suspended) the rest is dropped instead of simulated frame by frame.
+const MAX_LAG: Duration = Game::FRAME.saturating_mul(4);
+
+pub struct Engine {
+    /// The players, from left to right on the window (two in a versus, none out of the games).
+    players: Vec<Player>,
+    /// Personal best of the mode, compared with the current game.
+    pb: Option<Record>,
+    /// `true` if the game just ended set a new personal best.
+    new_pb: bool,
+    /// Connection with the other player of an online versus (who is the second player).
+    session: Option<Session>,
+    /// Server streaming the events of the games, if enabled.
+    spectators: Option<Spectators>,
+    /// Settings of the players.
+    config: Config,
+    /// Skin of the config.
+    skin: Skin,
+    /// Colors, styles and block textures of the window.
+    theme: Theme,
+    /// The screens shown, the last one on top (the program ends when there are none).
+    scenes: Vec<Scene>,
+    /// Inputs of the single-player game, recorded to save its replay.
+    replay: Option<Replay>,
+    /// Clipboard of the system, once the window is open.
+    clipboard: Option<ClipboardUtil>,
+    /// Game controllers plugged in, once the window is open.
+    pads: Pads,
+    /// Sound of the window, once it's open (`None` without an audio device).
+    audio: Option<Audio>,
+    /// What happens in the games, to play its sounds.
+    cues: Cues,
+    /// Part of the window the layout is drawn on, in its scaled coordinates (see `interface::fit`).
+    area: Rect,
+    /// Message shown at the bottom of the window over the scenes, with when it was shown.
+    toast: Option<(String, Instant)>,
 }
 
 impl Engine {
+    /// Creates an engine showing the title, from which the player chooses a mode.
+    pub fn title() -> Self {
+        Self::with_players(Vec::new(), None)
+    }
+    /// Creates an engine starting `game` (the title is shown after it).
     pub fn new(game: Game) -> Self {
-        Self {
-            game,
+        Self::with_players(vec![game], None)
+    }
+    /// Creates an engine where two players play against each other on the same keyboard.
+    pub fn versus(left: Game, right: Game) -> Self {
+        Self::with_players(vec![left, right], None)
+    }
+    /// Creates an engine where the local player plays against the other peer of `session`.
+    pub fn online(session: Session) -> Self {
+        Self::with_players(session.games().into(), Some(session))
+    }
+    /// Creates an engine playing `games` with the default config.
+    fn with_players(games: Vec<Game>, session: Option<Session>) -> Self {
+        let mut engine = Self {
+            pb: match games.as_slice() {
+                [game] => records::load(game.mode),
+                _ => None,
+            },
+            replay: match games.as_slice() {
+                [game] => Some(Replay::new(game)),
+                _ => None,
+            },
+            players: games.into_iter().map(|game| Player { game, bindings: Bindings::default() }).collect(),
+            new_pb: false,
+            session,
+            spectators: None,
+            config: Config::default(),
+            skin: Skin::default(),
+            theme: Theme::default(),
+            scenes: Vec::new(),
+            clipboard: None,
+            pads: Pads::default(),
+            audio: None,
+            cues: Cues::default(),
+            area: Rect::new(0, 0, interface::WIDTH, interface::HEIGHT),
+            toast: None,
+        };
+        engine.configure(Config::default()).expect("the default keys are known by SDL");
+        engine.open_scenes();
+        engine
+    }
+    /// Plays with the settings of `config`: the keys and the buttons, the handling and the delays
+    /// (the session's ones online), the next pieces shown, the ghost, the grid, the skin, the size
+    /// of the window, the font and the volumes.
+    /// # Returns
+    /// an error if a key or a button isn't known by SDL or the skin doesn't exist.
+    pub fn configure(&mut self, config: Config) -> Result<(), String> {
+        self.skin = Skin::from_name(&config.skin).ok_or_else(|| {
+            let names: Vec<_> = Skin::ALL.iter().map(|skin| format!("{:?}", skin.name())).collect();
+            format!("unknown skin {:?} (expected {})", config.skin, names.join(", "))
+        })?;
+        self.pads.configure(&config.controllers)?;
+        if let Some(audio) = &mut self.audio {
+            audio.set_volume(config.volume);
+        }
+        self.config = config;
+        self.bind()
+    }
+    /// Binds the keys of the config to the players and gives their games its handling
+    /// and its delays.
+    fn bind(&mut self) -> Result<(), String> {
+        let bindings = &self.config.bindings;
+        let keys = match (self.players.len(), &self.session) {
+            (2, None) => vec![(&bindings.left, "left"), (&bindings.right, "right")],
+            // the remote player has no keys
+            _ => vec![(&bindings.single, "single")],
+        };
+        for (player, (keys, section)) in self.players.iter_mut().zip(keys) {
+            player.bindings = Bindings::new(keys, section)?;
+        }
+        if self.session.is_none() {
+            for player in &mut self.players {
+                player.game.handling = self.config.handling.into();
+                player.game.delays = self.config.delays.into();
+            }
+        }
+        Ok(())
+    }
+    /// Returns the index of the bindings the settings show first (see `settings::SECTIONS`):
+    /// the ones of the left player in a versus.
+    fn section(&self) -> usize {
+        if self.players.len() > 1 { 1 } else { 0 }
+    }
+    /// Draws the window with the colors, the styles and the block textures of `theme`.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+    /// Streams the events of the games to the spectators connecting to `address`
+    /// (see `Spectators`).
+    pub fn spectate(&mut self, address: impl ToSocketAddrs) -> io::Result<()> {
+        self.spectators = Some(Spectators::bind(address)?);
+        Ok(())
+    }
+    /// Returns `true` if the game (or the match) is over.
+    fn is_over(&self) -> bool {
+        self.players.iter().any(|player| player.game.outcome.is_some())
+    }
+    /// Saves the record of the game if it's a new personal best, and its replay.
+    /// # Returns
+    /// the entry of the game and its rank if it makes its leaderboard (its player names it
+    /// before it's saved).
+    fn finish(&mut self) -> Option<(Entry, usize)> {
+        if self.players.len() > 1 {
+            return None;
+        }
+        let game = &self.players[0].game;
+        match records::save_if_best(game, self.pb.as_ref()) {
+            Ok(new_pb) => self.new_pb = new_pb,
+            Err(err) => eprintln!("Cannot save the personal best: {err}"),
+        }
+        // a game loaded from a fumen isn't recorded, nor ranked
+        let mut replay = self.replay.take().filter(|_| game.started)?;
+        replay.finish(game);
+        let path = match replays::save(&replay) {
+            Ok(path) => Some(path),
+            Err(err) => {
+                eprintln!("Cannot save the replay: {err}");
+                None
+            }
+        };
+        let entries = leaderboards::load();
+        let mut entry = Entry::new(game, path)?;
+        let rank = leaderboards::rank(&entries, &entry)?;
+        entry.name = leaderboards::last_name(&entries);
+        Some((entry, rank))
+    }
+    /// Starts new games in `mode` (two for a versus).
+    fn start(&mut self, mode: Mode) -> Result<(), String> {
+        let players = if mode == Mode::Versus { 2 } else { 1 };
+        self.players = (0..players)
+            .map(|_| Player { game: Game::new(mode), bindings: Bindings::default() })
+            .collect();
+        self.bind()?;
+        self.restart();
+        Ok(())
+    }
+    /// Starts new games in the same mode.
+    fn restart(&mut self) {
+        for player in &mut self.players {
+            player.game = Game::new(player.game.mode);
+            player.game.handling = self.config.handling.into();
+            player.game.delays = self.config.delays.into();
+        }
+        let [player] = self.players.as_slice() else {
+            self.pb = None;
+            self.replay = None;
+            self.new_pb = false;
+            self.reset_spectators();
+            return;
+        };
+        self.pb = records::load(player.game.mode);
+        self.replay = Some(Replay::new(&player.game));
+        self.new_pb = false;
+        self.reset_spectators();
+    }
+    /// Starts playing back `replay`.
+    fn start_replay(&mut self, replay: &Replay) {
+        self.players = vec![Player { game: replay.game(), bindings: Bindings::default() }];
+        self.pb = None;
+        self.new_pb = false;
+        self.replay = None;
+        self.reset_spectators();
+    }
+    /// Ends the games (and the online session).
+    fn leave(&mut self) {
+        self.players.clear();
+        self.session = None;
+        self.pb = None;
+        self.new_pb = false;
+        self.replay = None;
+        self.reset_spectators();
+    }
+    /// Releases the keys held by the local players.
+    fn release_all(&mut self) {
+        if let Some(session) = &mut self.session {
+            Action::ALL.into_iter().for_each(|action| session.release(action));
+            return;
+        }
+        for player in &mut self.players {
+            for action in Action::ALL {
+                player.game.release(action);
+                if let Some(replay) = &mut self.replay {
+                    replay.record(&player.game, action, false);
+                }
+            }
         }
     }
+    /// Sends the new games to the spectators.
+    fn reset_spectators(&mut self) {
+        if let Some(spectators) = &mut self.spectators {
+            spectators.reset(self.players.iter().map(|player| &player.game));
+        }
+    }
+    /// Simulates a frame of every game (sending garbage between the players of a versus).
+    fn step(&mut self) {
+        game::versus::step(self.players.iter_mut().map(|player| &mut player.game));
+    }
+    /// Sends the local inputs of `frames` frames and simulates the games up to them
+    /// (predicting the inputs of the other player, see `Session::advance`).
+    ///
+    /// The session is closed if the other player leaves after the end of the match, and the
+    /// match is interrupted on any other error (see `Scene::Interrupted`).
+    fn step_online(&mut self, frames: u32) {
+        let Some(session) = &mut self.session else { return };
+        let [local, remote] = self.players.as_mut_slice() else {
+            unreachable!("an online versus has two players");
+        };
+        let result = (0..frames)
+            .try_for_each(|_| session.tick())
+            .and_then(|_| session.advance(&mut local.game, &mut remote.game));
+        match result {
+            Ok(()) => {}
+            Err(net::Error::Disconnected) if self.is_over() => self.session = None,
+            // the games stop where they are, under the reason, until the player leaves them
+            Err(err) => {
+                self.session = None;
+                self.scenes.push(Scene::Interrupted(err));
+            }
+        }
+    }
+    /// Handles a press (`pressed`) or a release of `key` by a local player.
+    fn input(&mut self, key: Keycode, pressed: bool) {
+        for i in 0..self.players.len() {
+            if let Some(action) = self.players[i].bindings.action(key) {
+                self.act(i, action, pressed);
+            }
+        }
+    }
+    /// Presses (`pressed`) or releases `action` for the local player `i`
+    /// (the last local player if there are fewer).
+    fn act(&mut self, i: usize, action: Action, pressed: bool) {
+        let locals = if self.session.is_some() { 1 } else { self.players.len() };
+        let Some(player) = self.players.get_mut(i.min(locals.saturating_sub(1))) else { return };
+        if let Some(replay) = &mut self.replay {
+            replay.record(&player.game, action, pressed);
+        }
+        match (&mut self.session, pressed) {
+            (Some(session), true) => session.press(action),
+            (Some(session), false) => session.release(action),
+            (None, true) => player.game.update(action),
+            (None, false) => player.game.release(action),
+        }
+        // every game of a versus starts with the first input
+        if self.session.is_none() {
+            game::versus::start(self.players.iter_mut().map(|player| &mut player.game));
+        }
+    }
+    /// Handles a press of `button` on the controller whose joystick is `id`: its action during
+    /// the games (Start and Back pause them), the key it stands for in the menus
+    /// (see `pads::menu_key`).
+    fn button_down(&mut self, id: u32, button: Button) -> Result<(), Box<dyn StdError>> {
+        let playing = matches!(self.scenes.last(), Some(Scene::Playing));
+        match self.pads.button(id, button) {
+            Some((player, action)) if playing => self.act(player, action, true),
+            _ if playing && matches!(button, Button::Start | Button::Back) => self.key_down(Keycode::Escape, false)?,
+            _ if playing => {}
+            _ => {
+                if let Some(key) = pads::menu_key(button) {
+                    self.key_down(key, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Handles a release of `button` on the controller whose joystick is `id`.
+    fn button_up(&mut self, id: u32, button: Button) {
+        if let (Some((player, action)), Some(Scene::Playing)) = (self.pads.button(id, button), self.scenes.last()) {
+            self.act(player, action, false);
+        }
+    }
+    /// Handles a move of `axis` to `value` on the controller whose joystick is `id`.
+    fn stick(&mut self, id: u32, axis: Axis, value: i16) {
+        let Some((player, changes)) = self.pads.stick(id, axis, value) else { return };
+        // its tilt is followed out of the games too, but only plays during them
+        if matches!(self.scenes.last(), Some(Scene::Playing)) {
+            for (action, pressed) in changes {
+                self.act(player, action, pressed);
+            }
+        }
+    }
+    /// Shows `message` at the bottom of the window for `TOAST_TIME`.
+    fn toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now()));
+    }
+    /// Forgets the controller whose joystick is `id`, releasing the actions of its player.
+    fn unplug(&mut self, id: u32) {
+        let Some(player) = self.pads.unplug(id) else { return };
+        self.toast(format!("Controller disconnected (player {})", player + 1));
+        if matches!(self.scenes.last(), Some(Scene::Playing)) {
+            for action in Action::ALL {
+                self.act(player, action, false);
+            }
+        }
+    }
+    /// Copies the board (`whole` game) of the first player as a fumen.
+    fn copy_fumen(&mut self, whole: bool) -> Result<(), Box<dyn StdError>> {
+        let (Some(clipboard), Some(player)) = (&self.clipboard, self.players.first()) else { return Ok(()) };
+        let pages = if whole {
+            fumen::game_pages(&player.game)
+        } else {
+            vec![fumen::Page::from(&player.game)]
+        };
+        clipboard.set_clipboard_text(&fumen::encode(&pages)?)?;
+        Ok(())
+    }
+    /// Loads the first page of the copied fumen as the game of the first player
+    /// (it isn't recorded, its board doesn't come from its seed).
+    fn paste_fumen(&mut self) -> Result<(), Box<dyn StdError>> {
+        let Some(clipboard) = &self.clipboard else { return Ok(()) };
+        match fumen::decode(&clipboard.clipboard_text()?) {
+            Ok(pages) if !pages.is_empty() => {
+                self.players[0].game = pages.into_iter().next().unwrap().into();
+                self.players[0].game.handling = self.config.handling.into();
+                self.players[0].game.delays = self.config.delays.into();
+                self.replay = None;
+                self.reset_spectators();
+            }
+            Ok(_) => eprintln!("The fumen has no pages"),
+            Err(err) => eprintln!("Cannot load the fumen: {err}"),
+        }
+        Ok(())
+    }
+    /// Saves an image of the games in the screenshots' directory (nothing out of the games).
+    fn screenshot(&self) {
+        if self.players.is_empty() {
+            return;
+        }
+        let games: Vec<_> = self.players.iter().map(|player| &player.game).collect();
+        match screenshot::take(&games, &self.config, &self.theme) {
+            Ok(path) => eprintln!("Screenshot saved to {}", path.display()),
+            Err(err) => eprintln!("Cannot save the screenshot: {err}"),
+        }
+    }
+    /// Plays the sounds of what happened in the games since the last call, and the music while
+    /// they are played (faster as the stacks get close to the top).
+    fn play_sounds(&mut self) {
+        let Some(audio) = &mut self.audio else { return };
+        for sound in self.cues.update(self.players.iter().map(|player| &player.game)) {
+            audio.play(sound);
+        }
+        let playing = matches!(self.scenes.last(), Some(Scene::Playing | Scene::Replay { .. }))
+            && self.players.iter().all(|player| player.game.outcome.is_none());
+        audio.set_music(playing, cues::tempo(self.players.iter().map(|player| &player.game)));
+    }
+    /// Draws the games, each player on its own part of the window.
+    fn draw_games(&mut self, canvas: &mut Canvas<Window>, texts: &mut Texts, atlas: Option<&Atlas>) -> Result<(), Box<dyn StdError>> {
+        let blocks = Blocks {
+            skin: self.skin,
+            theme: &self.theme,
+            atlas,
+            colors: self.config.palette.colors(&self.theme),
+            patterns: self.config.patterns,
+        };
+        let width = interface::width(self.config.stats);
+        for (i, player) in self.players.iter_mut().enumerate() {
+            canvas.set_viewport(Rect::new(
+                self.area.x() + i as i32 * width as i32,
+                self.area.y(),
+                width,
+                interface::HEIGHT,
+            ));
+            interface::draw(canvas, texts, &mut player.game, self.pb.as_ref(), &self.config, &blocks)?;
+        }
+        canvas.set_viewport(self.area);
+        Ok(())
+    }
+    /// Draws the results of the ended games over them.
+    fn draw_results(&mut self, canvas: &mut Canvas<Window>, texts: &mut Texts) -> Result<(), Box<dyn StdError>> {
+        let width = interface::width(self.config.stats);
+        for (i, player) in self.players.iter().enumerate() {
+            canvas.set_viewport(Rect::new(
+                self.area.x() + i as i32 * width as i32,
+                self.area.y(),
+                width,
+                interface::HEIGHT,
+            ));
+            interface::draw_results(canvas, texts, &player.game, self.pb.as_ref(), self.new_pb, self.session.is_none())?;
+        }
+        canvas.set_viewport(self.area);
+        Ok(())
+    }
     /// Runs the program and draws it via `interface` module.
     pub fn run(&mut self) -> Result<(), Box<dyn StdError>> {
         let sdl = sdl2::init()?;
         let ttf = sdl2::ttf::init()?;
         let video = sdl.video()?;
+        let controllers = sdl.game_controller()?;
+        // the game is played without sound rather than not at all
+        self.audio = Audio::open(&sdl, self.config.volume)
+            .inspect_err(|err| eprintln!("Playing without sound: {err}"))
+            .ok();
+        self.clipboard = Some(video.clipboard());
 
-        let window = video
-            .window("Rusty Tetris", interface::WIDTH, interface::HEIGHT)
+        // as wide as the players (resized when their number or the layout changes)
+        let width = |players: usize, stats: bool| interface::width(stats) * players.max(1) as u32;
+        let mut last_width = width(self.players.len(), self.config.stats);
+        // the scale of the config, shrunk to leave room for the title bar on the screen
+        let scale = video.display_usable_bounds(0).map_or(self.config.scale, |bounds| {
+            let fit = (bounds.width() as f32 / last_width as f32).min(bounds.height() as f32 / interface::HEIGHT as f32);
+            self.config.scale.min(fit * 0.95)
+        });
+        let mut window = video
+            .window("Rusty Tetris", (last_width as f32 * scale) as u32, (interface::HEIGHT as f32 * scale) as u32)
             .position_centered()
+            .resizable()
+            .allow_highdpi()
             .build()?;
+        if self.config.fullscreen {
+            window.set_fullscreen(FullscreenType::Desktop)?;
+        }
 
         let mut canvas = window
             .into_canvas()
@@ -49,7 +539,17 @@ impl Engine {
 
         canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
 
-        let mut last_tick = Instant::now();
+        let texture_creator = canvas.texture_creator();
+        let mut texts = Texts::new(&ttf, &texture_creator, self.config.font.as_deref())?;
+        texts.set_color(self.theme.hud.text.into());
+        let atlas = match &self.theme.atlas {
+            Some(path) => Some(Atlas::load(&texture_creator, path, self.theme.ghost.alpha)?),
+            None => None,
+        };
+
+        let mut last_frame = Instant::now();
+        // time not simulated yet
+        let mut lag = Duration::ZERO;
 
         let mut event_pump = sdl.event_pump()?;
 
@@ -58,34 +558,85 @@ impl Engine {
             for event in event_pump.poll_iter() {
                 match event {
                     Event::Quit { .. } => break 'running,
-                    KeyDown { keycode: Some(key), .. } => {
-                        if key == Keycode::Escape {
-                            break 'running;
-                        }
-                        self.game.update(key);
+                    KeyDown { keycode: Some(Keycode::F11), repeat: false, .. } => {
+                        let window = canvas.window_mut();
+                        window.set_fullscreen(match window.fullscreen_state() {
+                            FullscreenType::Off => FullscreenType::Desktop,
+                            _ => FullscreenType::Off,
+                        })?;
                     }
-                    KeyUp { keycode: Some(Keycode::S | Keycode::Down), .. } => {
-                        self.game.tick = Game::BASE_TICK;
+                    KeyDown { keycode: Some(Keycode::F12), repeat: false, .. } => self.screenshot(),
+                    KeyDown { keycode: Some(key), repeat, .. } => self.key_down(key, repeat)?,
+                    KeyUp { keycode: Some(key), .. } if matches!(self.scenes.last(), Some(Scene::Playing)) => {
+                        self.input(key, false);
                     }
+                    // the controllers already plugged in are added when SDL starts too
+                    Event::ControllerDeviceAdded { which, .. } => match controllers.open(which) {
+                        Ok(controller) => {
+                            let name = controller.name();
+                            let player = self.pads.plug(controller, &self.config.controllers)?;
+                            self.toast(format!("Controller connected: {name} (player {})", player + 1));
+                        }
+                        Err(err) => eprintln!("Cannot open the controller: {err}"),
+                    },
+                    Event::ControllerDeviceRemoved { which, .. } => self.unplug(which),
+                    Event::ControllerButtonDown { which, button, .. } => self.button_down(which, button)?,
+                    Event::ControllerButtonUp { which, button, .. } => self.button_up(which, button),
+                    Event::ControllerAxisMotion { which, axis, value, .. } => self.stick(which, axis, value),
                     _ => {}
                 }
             }
+            if self.scenes.is_empty() {
+                break;
+            }
 
-            if last_tick.elapsed() >= self.game.tick {
-                if !self.game.try_drop() {
-                    self.game.place();
-                }
-                last_tick = Instant::now();
+            lag = (lag + last_frame.elapsed()).min(MAX_LAG);
+            last_frame = Instant::now();
+            let mut frames = 0;
+            while lag >= Game::FRAME {
+                self.frame();
+                frames += 1;
+                lag -= Game::FRAME;
+            }
+            self.step_online(frames);
+            self.play_sounds();
+            self.check_over();
+            if let Some(spectators) = &mut self.spectators {
+                spectators.update(self.players.iter().map(|player| &player.game));
             }
 
-            canvas.set_draw_color(Color::BLACK);
+            // a window as high, as wide as the players
+            let width = width(self.players.len(), self.config.stats);
+            if width != last_width && canvas.window().fullscreen_state() == FullscreenType::Off {
+                let height = canvas.window().size().1;
+                canvas.window_mut().set_size(height * width / interface::HEIGHT, height)?;
+            }
+            last_width = width;
+
+            // the layout follows the size of the window (in pixels, more than its size on high DPI
+            // screens) as it's resized
+            let (scale, area) = interface::fit(canvas.output_size()?, width);
+            canvas.set_scale(scale, scale)?;
+            self.area = area;
+            canvas.set_viewport(area);
+
+            // black bars around the layout
+            canvas.set_draw_color(Color::from(self.theme.background.window));
             canvas.clear();
 
-            interface::draw(&mut canvas, &ttf, &mut self.game)?;
+            self.draw_scenes(&mut canvas, &mut texts, atlas.as_ref(), width)?;
+            self.toast = self.toast.take().filter(|(_, shown)| shown.elapsed() < TOAST_TIME);
+            if let Some((toast, _)) = &self.toast {
+                interface::draw_toast(&mut canvas, &mut texts, toast)?;
+            }
+            texts.sweep();
 
             canvas.present();
 
-            std::thread::sleep(Duration::from_millis(16));
+            // waits for the next frame only
+            if let Some(left) = Game::FRAME.checked_sub(lag + last_frame.elapsed()) {
+                std::thread::sleep(left);
+            }
         }
 
         Ok(())
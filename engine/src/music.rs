@@ -0,0 +1,79 @@
+use crate::audio::{Wave, frequency};
+
+/// Eighth notes per second at the normal tempo (150 quarter notes per minute).
+const EIGHTHS_PER_SECOND: f32 = 5.0;
+/// The melody (Korobeiniki, a Russian folk song): each note with its semitones above the
+/// A at 440 Hz (`None` for a rest) and its length in eighth notes.
+const MELODY: [(Option<i32>, u32); 40] = [
+    (Some(7), 2), (Some(2), 1), (Some(3), 1), (Some(5), 2), (Some(3), 1), (Some(2), 1),
+    (Some(0), 2), (Some(0), 1), (Some(3), 1), (Some(7), 2), (Some(5), 1), (Some(3), 1),
+    (Some(2), 3), (Some(3), 1), (Some(5), 2), (Some(7), 2),
+    (Some(3), 2), (Some(0), 2), (Some(0), 2), (None, 2),
+    (None, 1), (Some(5), 2), (Some(8), 1), (Some(12), 2), (Some(10), 1), (Some(8), 1),
+    (Some(7), 3), (Some(3), 1), (Some(7), 2), (Some(5), 1), (Some(3), 1),
+    (Some(2), 2), (Some(2), 1), (Some(3), 1), (Some(5), 2), (Some(7), 2),
+    (Some(3), 2), (Some(0), 2), (Some(0), 2), (None, 2),
+];
+/// Root of the bass of each bar, in semitones above the A at 440 Hz (it alternates with the
+/// octave above on each eighth note).
+const BASS: [i32; 8] = [-29, -24, -29, -24, -31, -21, -29, -24];
+/// Eighth notes of a bar.
+const BAR: u32 = 8;
+
+/// The music of the games, looping, played faster as the stacks get close to the top.
+pub(crate) struct Music {
+    /// `false` while it's paused.
+    pub(crate) playing: bool,
+    /// Times the normal speed it's played at.
+    pub(crate) tempo: f32,
+    /// Eighth notes played since the start of the loop.
+    position: f32,
+    /// Phases of the waves of the melody and of the bass (0 to 1).
+    phases: (f32, f32),
+}
+
+impl Default for Music {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            tempo: 1.0,
+            position: 0.0,
+            phases: (0.0, 0.0),
+        }
+    }
+}
+
+impl Music {
+    /// Returns the next sample at `rate` samples per second (silence while it's paused).
+    pub(crate) fn sample(&mut self, rate: f32) -> f32 {
+        if !self.playing {
+            return 0.0;
+        }
+        let length = BAR * BASS.len() as u32;
+        let eighth = self.position as u32;
+        // the note of the melody playing, with the eighth note it started on
+        let (mut start, mut melody) = (0, (None, 1));
+        for &(note, eighths) in &MELODY {
+            if start + eighths > eighth {
+                melody = (note, eighths);
+                break;
+            }
+            start += eighths;
+        }
+        let mut sample = 0.0;
+        if let (Some(semitones), eighths) = melody {
+            // fades out, with a gap before the next note
+            let progress = (self.position - start as f32) / eighths as f32;
+            let envelope = if progress < 0.9 { 1.0 - progress * 0.5 } else { 0.0 };
+            sample += Wave::Pulse(0.25).at(self.phases.0, 0.0) * 0.25 * envelope;
+            self.phases.0 = (self.phases.0 + frequency(semitones) / rate).fract();
+        }
+        let octave = if eighth.is_multiple_of(2) { 0 } else { 12 };
+        let bass = BASS[(eighth / BAR) as usize % BASS.len()] + octave;
+        let envelope = 1.0 - self.position.fract() * 0.7;
+        sample += Wave::Triangle.at(self.phases.1, 0.0) * 0.35 * envelope;
+        self.phases.1 = (self.phases.1 + frequency(bass) / rate).fract();
+        self.position = (self.position + EIGHTHS_PER_SECOND * self.tempo / rate) % length as f32;
+        sample
+    }
+}
@@ -0,0 +1,203 @@
+use crate::bindings::Buttons;
+use config::Controllers;
+use game::Action;
+use sdl2::{
+    controller::{Axis, Button, GameController},
+    keyboard::Keycode,
+};
+
+/// A game controller plugged in.
+struct Pad {
+    /// Kept open to receive its events.
+    controller: GameController,
+    buttons: Buttons,
+    /// Player it plays for (0 for the first one).
+    player: usize,
+    stick: Stick,
+}
+
+/// What the left stick of a controller is tilted to, past the deadzone.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Stick {
+    /// The move it's tilted to.
+    shift: Option<Action>,
+    /// `true` if it's tilted down.
+    soft_drop: bool,
+}
+
+impl Stick {
+    /// Moves `axis` to `value`: the stick moves (X) and soft drops (down) once it's tilted past
+    /// `deadzone`.
+    /// # Returns
+    /// the actions pressed (`true`) or released by the move.
+    fn tilt(&mut self, axis: Axis, value: i16, deadzone: i16) -> Vec<(Action, bool)> {
+        let mut changes = Vec::new();
+        match axis {
+            Axis::LeftX => {
+                let shift = match value {
+                    value if value < -deadzone => Some(Action::MoveLeft),
+                    value if value > deadzone => Some(Action::MoveRight),
+                    _ => None,
+                };
+                if shift != self.shift {
+                    changes.extend(self.shift.map(|action| (action, false)));
+                    changes.extend(shift.map(|action| (action, true)));
+                    self.shift = shift;
+                }
+            }
+            // SDL's Y axis points down
+            Axis::LeftY if (value > deadzone) != self.soft_drop => {
+                self.soft_drop = value > deadzone;
+                changes.push((Action::SoftDrop, self.soft_drop));
+            }
+            _ => {}
+        }
+        changes
+    }
+}
+
+/// Returns the tilt of the sticks ignored out of `i16::MAX`, for a `deadzone` from 0 to 1.
+fn deadzone(deadzone: f32) -> i16 {
+    (deadzone.clamp(0.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Returns the first player without a controller, of the `players` of the controllers.
+fn free_player(players: &[usize]) -> usize {
+    (0..).find(|player| !players.contains(player)).unwrap()
+}
+
+/// The game controllers plugged in. Each one plays for the first player without a controller
+/// when it's connected, so that a controller plugged in again plays for the same player.
+#[derive(Default)]
+pub(crate) struct Pads {
+    pads: Vec<Pad>,
+    /// Tilt of the sticks ignored, out of `i16::MAX`.
+    deadzone: i16,
+}
+
+/// Returns the buttons of the config for the controller named `name`: its own if it has some.
+/// # Returns
+/// an error naming the first button SDL doesn't know.
+fn buttons(controllers: &Controllers, name: &str) -> Result<Buttons, String> {
+    match controllers.pads.get(name) {
+        Some(buttons) => Buttons::new(buttons, &format!("pads.{name:?}")),
+        None => Buttons::new(&controllers.buttons, "buttons"),
+    }
+}
+
+/// Returns the key `button` stands for out of the games: the D-pad moves in the menus,
+/// A and Start choose, B and Back go back.
+pub(crate) fn menu_key(button: Button) -> Option<Keycode> {
+    match button {
+        Button::DPadUp => Some(Keycode::Up),
+        Button::DPadDown => Some(Keycode::Down),
+        Button::DPadLeft => Some(Keycode::Left),
+        Button::DPadRight => Some(Keycode::Right),
+        Button::A | Button::Start => Some(Keycode::Return),
+        Button::B | Button::Back => Some(Keycode::Escape),
+        _ => None,
+    }
+}
+
+impl Pads {
+    /// Binds the buttons of `controllers` to the controllers plugged in, and uses its deadzone.
+    /// # Returns
+    /// an error naming the first button SDL doesn't know, of any controller of the config.
+    pub(crate) fn configure(&mut self, controllers: &Controllers) -> Result<(), String> {
+        Buttons::new(&controllers.buttons, "buttons")?;
+        for name in controllers.pads.keys() {
+            buttons(controllers, name)?;
+        }
+        for pad in &mut self.pads {
+            pad.buttons = buttons(controllers, &pad.controller.name())?;
+        }
+        self.deadzone = deadzone(controllers.deadzone);
+        Ok(())
+    }
+    /// Adds `controller`, with its buttons in `controllers`, for the first player without one.
+    /// # Returns
+    /// the player it plays for.
+    pub(crate) fn plug(&mut self, controller: GameController, controllers: &Controllers) -> Result<usize, String> {
+        let players: Vec<_> = self.pads.iter().map(|pad| pad.player).collect();
+        let player = free_player(&players);
+        self.pads.push(Pad {
+            buttons: buttons(controllers, &controller.name())?,
+            controller,
+            player,
+            stick: Stick::default(),
+        });
+        Ok(player)
+    }
+    /// Removes the controller whose joystick is `id`.
+    /// # Returns
+    /// the player it played for, `None` if it wasn't plugged in.
+    pub(crate) fn unplug(&mut self, id: u32) -> Option<usize> {
+        let i = self.pads.iter().position(|pad| pad.controller.instance_id() == id)?;
+        Some(self.pads.remove(i).player)
+    }
+    /// Returns the player of the controller whose joystick is `id` and the action bound to
+    /// `button`, `None` if it's unknown or the button isn't bound.
+    pub(crate) fn button(&self, id: u32, button: Button) -> Option<(usize, Action)> {
+        let pad = self.pads.iter().find(|pad| pad.controller.instance_id() == id)?;
+        Some((pad.player, pad.buttons.action(button)?))
+    }
+    /// Moves `axis` of the controller whose joystick is `id` to `value` (see `Stick::tilt`).
+    /// # Returns
+    /// the player of the controller, and the actions pressed (`true`) or released by the move.
+    pub(crate) fn stick(&mut self, id: u32, axis: Axis, value: i16) -> Option<(usize, Vec<(Action, bool)>)> {
+        let deadzone = self.deadzone;
+        let pad = self.pads.iter_mut().find(|pad| pad.controller.instance_id() == id)?;
+        Some((pad.player, pad.stick.tilt(axis, value, deadzone)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_scales_to_the_axis() {
+        assert_eq!(deadzone(0.0), 0);
+        assert_eq!(deadzone(0.5), i16::MAX / 2);
+        assert_eq!(deadzone(1.0), i16::MAX);
+        assert_eq!(deadzone(2.0), i16::MAX);
+    }
+
+    #[test]
+    fn stick_inside_the_deadzone_does_nothing() {
+        let mut stick = Stick::default();
+        assert!(stick.tilt(Axis::LeftX, -100, 100).is_empty());
+        assert!(stick.tilt(Axis::LeftX, 100, 100).is_empty());
+        assert!(stick.tilt(Axis::LeftY, 100, 100).is_empty());
+        assert_eq!(stick, Stick::default());
+    }
+
+    #[test]
+    fn stick_shifts_past_the_deadzone() {
+        let mut stick = Stick::default();
+        assert_eq!(stick.tilt(Axis::LeftX, -101, 100), [(Action::MoveLeft, true)]);
+        // holding it doesn't press again
+        assert!(stick.tilt(Axis::LeftX, i16::MIN, 100).is_empty());
+        assert_eq!(stick.tilt(Axis::LeftX, 101, 100), [(Action::MoveLeft, false), (Action::MoveRight, true)]);
+        assert_eq!(stick.tilt(Axis::LeftX, 0, 100), [(Action::MoveRight, false)]);
+    }
+
+    #[test]
+    fn stick_soft_drops_down_only() {
+        let mut stick = Stick::default();
+        assert!(stick.tilt(Axis::LeftY, i16::MIN, 100).is_empty());
+        assert_eq!(stick.tilt(Axis::LeftY, 101, 100), [(Action::SoftDrop, true)]);
+        assert_eq!(stick.tilt(Axis::LeftY, 50, 100), [(Action::SoftDrop, false)]);
+        assert!(stick.tilt(Axis::RightY, i16::MAX, 100).is_empty());
+    }
+
+    #[test]
+    fn controllers_play_for_the_first_free_player() {
+        assert_eq!(free_player(&[]), 0);
+        assert_eq!(free_player(&[0]), 1);
+        assert_eq!(free_player(&[0, 1]), 2);
+        // a controller plugged in again plays for the player it played for
+        assert_eq!(free_player(&[1, 2]), 0);
+        assert_eq!(free_player(&[0, 2]), 1);
+    }
+}
@@ -0,0 +1,158 @@
+use game::{Game, Mode, Outcome};
+use std::{
+    fs,
+    io,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Personal best of a mode, which the current game is compared with.
+#[derive(Clone)]
+pub(crate) enum Record {
+    /// Split times of the sprint.
+    Splits(Vec<Duration>),
+    /// Score of the marathon.
+    Score(u32),
+}
+
+/// Loads the personal best of `mode`, `None` if it has none (yet).
+pub(crate) fn load(mode: Mode) -> Option<Record> {
+    match mode {
+        Mode::Sprint => load_sprint_pb().map(Record::Splits),
+        Mode::Marathon { start_level, endless } => load_marathon_best(start_level, endless).map(Record::Score),
+        Mode::Endless | Mode::Ultra | Mode::Dig { .. } | Mode::Finesse | Mode::Versus => None,
+    }
+}
+
+/// Saves the record of an ended game if it beats `best`.
+/// # Returns
+/// `true` if it is a new personal best.
+pub(crate) fn save_if_best(game: &Game, best: Option<&Record>) -> io::Result<bool> {
+    match game.mode {
+        Mode::Sprint if game.outcome == Some(Outcome::Completed) => {
+            let splits = &game.stats.splits;
+            let new = match best {
+                Some(Record::Splits(pb)) => beats(splits, pb),
+                _ => true,
+            };
+            if new {
+                save_sprint_pb(splits)?;
+            }
+            Ok(new)
+        }
+        Mode::Marathon { start_level, endless } => {
+            let new = match best {
+                Some(Record::Score(pb)) => game.score > *pb,
+                _ => game.score > 0,
+            };
+            if new {
+                save_marathon_best(start_level, endless, game.score)?;
+            }
+            Ok(new)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Returns `true` if the sprint finished with `splits` beats the one of `pb`: it finished
+/// sooner, or the personal best has no splits.
+fn beats(splits: &[Duration], pb: &[Duration]) -> bool {
+    pb.last().is_none_or(|best| splits.last().is_some_and(|split| split < best))
+}
+
+/// Returns the directory where the records are saved.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("rusty-tetris"))
+}
+
+/// Loads the splits of the sprint's personal best (one time in milliseconds per line).
+fn load_sprint_pb() -> Option<Vec<Duration>> {
+    let text = fs::read_to_string(data_dir()?.join("sprint_pb.txt")).ok()?;
+    text.lines()
+        .map(|line| line.trim().parse().ok().map(Duration::from_millis))
+        .collect()
+}
+
+/// Saves the splits of a new sprint's personal best.
+fn save_sprint_pb(splits: &[Duration]) -> io::Result<()> {
+    let Some(dir) = data_dir() else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory"));
+    };
+    fs::create_dir_all(&dir)?;
+    let text: String = splits
+        .iter()
+        .map(|split| format!("{}\n", split.as_millis()))
+        .collect();
+    fs::write(dir.join("sprint_pb.txt"), text)
+}
+
+/// Loads the best score of the marathon for each start level (and variant).
+fn load_marathon_bests() -> Vec<(u32, bool, u32)> {
+    let Some(text) = data_dir().and_then(|dir| fs::read_to_string(dir.join("marathon_bests.txt")).ok()) else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut values = line.split_whitespace().map(str::parse::<u32>);
+            Some((values.next()?.ok()?, values.next()?.ok()? == 1, values.next()?.ok()?))
+        })
+        .collect()
+}
+
+/// Loads the best score of the marathon starting at `start_level`.
+fn load_marathon_best(start_level: u32, endless: bool) -> Option<u32> {
+    load_marathon_bests()
+        .into_iter()
+        .find(|(level, variant, _)| *level == start_level && *variant == endless)
+        .map(|(.., score)| score)
+}
+
+/// Saves a new best score of the marathon starting at `start_level`.
+fn save_marathon_best(start_level: u32, endless: bool, score: u32) -> io::Result<()> {
+    let Some(dir) = data_dir() else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory"));
+    };
+    let mut bests = load_marathon_bests();
+    bests.retain(|(level, variant, _)| *level != start_level || *variant != endless);
+    bests.push((start_level, endless, score));
+    bests.sort_unstable();
+    fs::create_dir_all(&dir)?;
+    let text: String = bests
+        .iter()
+        .map(|(level, variant, score)| format!("{} {} {}\n", level, *variant as u32, score))
+        .collect();
+    fs::write(dir.join("marathon_bests.txt"), text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splits(millis: &[u64]) -> Vec<Duration> {
+        millis.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn sooner_sprint_beats_the_pb() {
+        assert!(beats(&splits(&[1_000, 2_000]), &splits(&[1_500, 2_500])));
+        // only the final time counts
+        assert!(beats(&splits(&[2_000, 2_400]), &splits(&[1_000, 2_500])));
+    }
+
+    #[test]
+    fn slower_or_equal_sprint_doesnt_beat_the_pb() {
+        assert!(!beats(&splits(&[1_000, 3_000]), &splits(&[1_500, 2_500])));
+        assert!(!beats(&splits(&[1_000, 2_500]), &splits(&[1_500, 2_500])));
+    }
+
+    #[test]
+    fn any_sprint_beats_a_pb_without_splits() {
+        assert!(beats(&splits(&[60_000]), &[]));
+        assert!(beats(&[], &[]));
+    }
+
+    #[test]
+    fn sprint_without_splits_doesnt_beat_a_pb() {
+        assert!(!beats(&[], &splits(&[60_000])));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::records;
+use game::Replay;
+use std::{
+    cmp::Reverse,
+    fs,
+    io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Extension of the replay files.
+const EXTENSION: &str = "replay";
+
+/// A replay saved in the replays' directory.
+pub(crate) struct Saved {
+    /// Seconds since the Unix epoch when it was saved.
+    pub(crate) time: u64,
+    pub(crate) replay: Replay,
+}
+
+/// Returns the directory where the replays are saved.
+fn dir() -> Option<PathBuf> {
+    Some(records::data_dir()?.join("replays"))
+}
+
+/// Saves `replay` as `<mode>-<time>.replay`.
+/// # Returns
+/// the path of the file.
+pub(crate) fn save(replay: &Replay) -> io::Result<PathBuf> {
+    let Some(dir) = dir() else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory"));
+    };
+    fs::create_dir_all(&dir)?;
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = dir.join(format!("{}-{}.{EXTENSION}", replay.mode.name(), time.as_millis()));
+    fs::write(&path, replay.to_string())?;
+    Ok(path)
+}
+
+/// Loads the saved replays, newest first (the files which can't be read are skipped).
+pub(crate) fn list() -> Vec<Saved> {
+    let Some(entries) = dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut saved: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != EXTENSION {
+                return None;
+            }
+            let time = path.metadata().ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+            let replay = fs::read_to_string(&path).ok()?.parse().ok()?;
+            Some(Saved { time, replay })
+        })
+        .collect();
+    saved.sort_unstable_by_key(|saved| Reverse(saved.time));
+    saved
+}
+
+/// Formats `secs` since the Unix epoch as a UTC date, `yyyy-mm-dd hh:mm`.
+pub(crate) fn format_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let minutes = secs % 86_400 / 60;
+    // civil date from the days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year}-{month:02}-{day:02} {:02}:{:02}", minutes / 60, minutes % 60)
+}
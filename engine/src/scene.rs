@@ -0,0 +1,504 @@
+use crate::{
+    Engine,
+    interface::{self, Menu},
+    leaderboards::{self, Entry},
+    replays::{self, Saved},
+    settings::{Response, Settings},
+    skin::Atlas,
+    text::Texts,
+};
+use game::{Delays, Game, Mode, Replay};
+use std::{error::Error as StdError, fs};
+use sdl2::{
+    keyboard::Keycode,
+    render::Canvas,
+    video::Window,
+};
+
+/// Frames of the countdown before the games start.
+const COUNTDOWN: u32 = Game::FPS as u32 * 3 / 2;
+/// Frames of the countdown showing "GO" (the last ones).
+const GO: u32 = Game::FPS as u32 / 2;
+
+/// A screen of the program, which handles its own keys and drawing.
+pub(crate) enum Scene {
+    /// The name of the game and the main menu.
+    Title { selected: usize },
+    /// The modes to play, with the start level of the marathons and the garbage rows of the digs.
+    ModeSelect { selected: usize, level: u32, rows: u32 },
+    /// "READY" then "GO" before the games start, with the frames left.
+    Countdown(u32),
+    Playing,
+    /// The menu over the paused games (online, they go on).
+    Paused { selected: usize, rows: &'static [&'static str] },
+    /// The results over the ended games.
+    Results,
+    /// The error which stopped the online versus, over its games.
+    Interrupted(net::Error),
+    Settings(Box<Settings>),
+    /// The saved replays.
+    Replays { selected: usize, saved: Vec<Saved> },
+    /// A replay played back, with the index of its next input.
+    Replay { replay: Replay, next: usize },
+    /// The name of the player typed over the results of a game which made its leaderboard
+    /// at `rank` (0 for the first).
+    NameEntry { entry: Entry, rank: usize },
+    /// The leaderboard at `board` in the ones of the `entries` (see `leaderboards::boards`).
+    Leaderboards { board: usize, selected: usize, entries: Vec<Entry> },
+}
+
+/// What a scene does after handling a key.
+enum Transition {
+    /// Stays on top.
+    None,
+    /// Shows a scene over it.
+    Push(Scene),
+    /// Closes it.
+    Pop,
+    /// Shows another scene instead of it.
+    Replace(Scene),
+    /// Closes the scenes of the games and starts them again with a countdown.
+    Play,
+    /// Closes the scenes of the games and ends them.
+    Leave,
+    /// Ends the program.
+    Quit,
+}
+
+/// Rows of the title's menu.
+const TITLE_ROWS: [&str; 5] = ["PLAY", "REPLAYS", "LEADERBOARDS", "SETTINGS", "QUIT"];
+/// Rows of the mode select.
+const MODE_ROWS: [&str; 9] = ["ENDLESS", "SPRINT", "ULTRA", "MARATHON", "ENDLESS MARATHON", "DIG", "RISING DIG", "FINESSE", "VERSUS"];
+
+impl Scene {
+    /// Returns `true` if the scene shows games (it is closed when they are left).
+    fn is_game(&self) -> bool {
+        matches!(self, Self::Countdown(_) | Self::Playing | Self::Paused { .. } | Self::Results | Self::Interrupted(_) | Self::Replay { .. })
+    }
+    /// Returns `true` if the scene is drawn over the one below it.
+    fn is_overlay(&self) -> bool {
+        matches!(self, Self::Paused { .. } | Self::Results | Self::Interrupted(_) | Self::Settings(_) | Self::NameEntry { .. })
+    }
+    /// Returns the leaderboards with the one of `mode` and `delays` shown (the first one if
+    /// it has no entries), at its row `selected`.
+    fn leaderboards(entries: Vec<Entry>, mode: Mode, delays: Delays, selected: usize) -> Self {
+        let board = leaderboards::boards(&entries)
+            .iter()
+            .position(|&board| board == (mode, delays))
+            .unwrap_or(0);
+        Self::Leaderboards { board, selected, entries }
+    }
+    /// Returns the pause menu of an `online` game or of a `replay`.
+    fn paused(online: bool, replay: bool) -> Self {
+        let rows: &[&str] = match (online, replay) {
+            (true, _) => &["RESUME", "LEAVE"],
+            (_, true) => &["RESUME", "RESTART", "QUIT"],
+            _ => &["RESUME", "RESTART", "SETTINGS", "QUIT"],
+        };
+        Self::Paused { selected: 0, rows }
+    }
+}
+
+/// Returns the mode of the row `selected` of the mode select.
+fn selected_mode(selected: usize, level: u32, rows: u32) -> Mode {
+    match selected {
+        0 => Mode::Endless,
+        1 => Mode::Sprint,
+        2 => Mode::Ultra,
+        3 => Mode::Marathon { start_level: level, endless: false },
+        4 => Mode::Marathon { start_level: level, endless: true },
+        5 => Mode::Dig { rows, rising: false },
+        6 => Mode::Dig { rows, rising: true },
+        7 => Mode::Finesse,
+        _ => Mode::Versus,
+    }
+}
+
+/// Returns the character typed with `key` in a name: a letter (upper case), a digit or a space.
+fn typed(key: Keycode) -> Option<char> {
+    if key == Keycode::Space {
+        return Some(' ');
+    }
+    let name = key.name();
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphanumeric() => Some(c.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Moves `selected` up or down a menu of `len` rows (wrapping around) if `key` is Up or Down.
+fn navigate(selected: &mut usize, len: usize, key: Keycode) {
+    match key {
+        Keycode::Up => *selected = (*selected + len - 1) % len,
+        Keycode::Down => *selected = (*selected + 1) % len,
+        _ => {}
+    }
+}
+
+/// Returns `value` moved by one towards Left or Right within `min..=max`.
+fn adjust(value: u32, key: Keycode, min: u32, max: u32) -> u32 {
+    match key {
+        Keycode::Left => value.saturating_sub(1).max(min),
+        Keycode::Right => (value + 1).min(max),
+        _ => value,
+    }
+}
+
+impl Engine {
+    /// Shows the title, and over it the games with a countdown if there are any
+    /// (online, they start at once).
+    pub(crate) fn open_scenes(&mut self) {
+        self.scenes = vec![Scene::Title { selected: 0 }];
+        if self.session.is_some() {
+            self.scenes.push(Scene::Playing);
+        } else if !self.players.is_empty() {
+            self.scenes.push(Scene::Countdown(COUNTDOWN));
+        }
+    }
+    /// Handles a press of `key` (`repeat` if it is held) by the scene on top.
+    pub(crate) fn key_down(&mut self, key: Keycode, repeat: bool) -> Result<(), Box<dyn StdError>> {
+        let Some(mut scene) = self.scenes.pop() else { return Ok(()) };
+        let transition = match &mut scene {
+            Scene::Title { selected } => match key {
+                Keycode::Escape => Transition::Quit,
+                Keycode::Return if !repeat => match *selected {
+                    0 => Transition::Push(Scene::ModeSelect { selected: 0, level: 1, rows: 10 }),
+                    1 => Transition::Push(Scene::Replays { selected: 0, saved: replays::list() }),
+                    2 => Transition::Push(Scene::Leaderboards { board: 0, selected: 0, entries: leaderboards::load() }),
+                    3 => Transition::Push(Scene::Settings(Box::new(Settings::new(self.config.clone(), 0)))),
+                    _ => Transition::Quit,
+                },
+                _ => {
+                    navigate(selected, TITLE_ROWS.len(), key);
+                    Transition::None
+                }
+            },
+            Scene::ModeSelect { selected, level, rows } => match key {
+                Keycode::Escape => Transition::Pop,
+                Keycode::Return if !repeat => {
+                    self.start(selected_mode(*selected, *level, *rows))?;
+                    Transition::Push(Scene::Countdown(COUNTDOWN))
+                }
+                Keycode::Left | Keycode::Right => {
+                    match *selected {
+                        3 | 4 => *level = adjust(*level, key, 1, Mode::MAX_LEVEL),
+                        5 | 6 => *rows = adjust(*rows, key, 1, Mode::MAX_DIG_ROWS),
+                        _ => {}
+                    }
+                    Transition::None
+                }
+                _ => {
+                    navigate(selected, MODE_ROWS.len(), key);
+                    Transition::None
+                }
+            },
+            Scene::Countdown(_) if key == Keycode::Escape => Transition::Push(Scene::paused(false, false)),
+            Scene::Countdown(_) => Transition::None,
+            Scene::Playing => self.key_playing(key, repeat)?,
+            Scene::Paused { selected, rows } => match key {
+                Keycode::Escape => Transition::Pop,
+                Keycode::Return if !repeat => match rows[*selected] {
+                    "RESUME" => Transition::Pop,
+                    "RESTART" => self.retry(),
+                    "SETTINGS" => Transition::Push(Scene::Settings(Box::new(Settings::new(self.config.clone(), self.section())))),
+                    _ => Transition::Leave,
+                },
+                _ => {
+                    navigate(selected, rows.len(), key);
+                    Transition::None
+                }
+            },
+            Scene::Results => match key {
+                Keycode::Escape => Transition::Leave,
+                Keycode::Return if !repeat && self.session.is_none() => self.retry(),
+                _ => Transition::None,
+            },
+            Scene::Interrupted(_) => match key {
+                Keycode::Escape | Keycode::Return if !repeat => Transition::Leave,
+                _ => Transition::None,
+            },
+            Scene::Settings(settings) => match settings.key(key, repeat) {
+                Response::None => Transition::None,
+                Response::Changed => {
+                    self.configure(settings.config.clone())?;
+                    Transition::None
+                }
+                Response::Close => Transition::Pop,
+            },
+            Scene::Replays { selected, saved } => match key {
+                Keycode::Escape => Transition::Pop,
+                Keycode::Return if !repeat && !saved.is_empty() => {
+                    let replay = saved[*selected].replay.clone();
+                    self.start_replay(&replay);
+                    Transition::Push(Scene::Replay { replay, next: 0 })
+                }
+                _ => {
+                    navigate(selected, saved.len().max(1), key);
+                    Transition::None
+                }
+            },
+            Scene::Replay { .. } if key == Keycode::Escape => Transition::Push(Scene::paused(false, true)),
+            Scene::Replay { .. } => Transition::None,
+            Scene::NameEntry { entry, rank } => match key {
+                Keycode::Escape => Transition::Pop,
+                Keycode::Return if !repeat => match leaderboards::save(entry.clone()) {
+                    Ok(entries) => Transition::Replace(Scene::leaderboards(entries, entry.mode, entry.delays, *rank)),
+                    Err(err) => {
+                        eprintln!("Cannot save the leaderboard: {err}");
+                        Transition::Pop
+                    }
+                },
+                Keycode::Backspace => {
+                    entry.name.pop();
+                    Transition::None
+                }
+                _ => {
+                    if let Some(c) = typed(key).filter(|_| entry.name.len() < leaderboards::MAX_NAME) {
+                        entry.name.push(c);
+                    }
+                    Transition::None
+                }
+            },
+            Scene::Leaderboards { board, selected, entries } => {
+                let boards = leaderboards::boards(entries);
+                let shown = boards.get(*board).map_or_else(Vec::new, |&(mode, delays)| leaderboards::board(entries, mode, delays));
+                match key {
+                    Keycode::Escape => Transition::Pop,
+                    Keycode::Return if !repeat => match shown.get(*selected).and_then(|entry| entry.replay.as_ref()) {
+                        Some(path) => match fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|text| text.parse::<Replay>().map_err(|err| err.to_string())) {
+                            Ok(replay) => {
+                                self.start_replay(&replay);
+                                Transition::Push(Scene::Replay { replay, next: 0 })
+                            }
+                            Err(err) => {
+                                eprintln!("Cannot load the replay: {err}");
+                                Transition::None
+                            }
+                        },
+                        None => Transition::None,
+                    },
+                    Keycode::Left | Keycode::Right if !boards.is_empty() => {
+                        *board = if key == Keycode::Left { (*board + boards.len() - 1) % boards.len() } else { (*board + 1) % boards.len() };
+                        *selected = 0;
+                        Transition::None
+                    }
+                    _ => {
+                        navigate(selected, shown.len().max(1), key);
+                        Transition::None
+                    }
+                }
+            }
+        };
+        self.apply(scene, transition);
+        Ok(())
+    }
+    /// Handles a press of `key` during the games.
+    fn key_playing(&mut self, key: Keycode, repeat: bool) -> Result<Transition, Box<dyn StdError>> {
+        match key {
+            Keycode::Escape => {
+                self.release_all();
+                return Ok(Transition::Push(Scene::paused(self.session.is_some(), false)));
+            }
+            Keycode::Tab if self.session.is_none() => {
+                self.release_all();
+                return Ok(Transition::Push(Scene::Settings(Box::new(Settings::new(self.config.clone(), self.section())))));
+            }
+            // copy the board / the whole game as a fumen
+            Keycode::C | Keycode::X => self.copy_fumen(key == Keycode::X)?,
+            // load the first page of the copied fumen
+            Keycode::V if self.session.is_none() => self.paste_fumen()?,
+            // the game repeats the held keys itself (see `Handling`)
+            _ if repeat => {}
+            _ => self.input(key, true),
+        }
+        Ok(Transition::None)
+    }
+    /// Starts the games again, or the replay under the scene on top.
+    fn retry(&mut self) -> Transition {
+        if let Some(Scene::Replay { replay, next }) = self.scenes.last_mut() {
+            self.players[0].game = replay.game();
+            *next = 0;
+            self.reset_spectators();
+            return Transition::Pop;
+        }
+        self.restart();
+        Transition::Play
+    }
+    /// Does `transition` from `scene` (the one on top, taken out of the stack).
+    fn apply(&mut self, scene: Scene, transition: Transition) {
+        match transition {
+            Transition::None => self.scenes.push(scene),
+            Transition::Push(new) => {
+                self.scenes.push(scene);
+                self.scenes.push(new);
+            }
+            Transition::Pop => {}
+            Transition::Replace(new) => self.scenes.push(new),
+            Transition::Play => {
+                self.scenes.push(scene);
+                self.scenes.retain(|scene| !scene.is_game());
+                self.scenes.push(Scene::Countdown(COUNTDOWN));
+            }
+            Transition::Leave => {
+                self.scenes.push(scene);
+                self.scenes.retain(|scene| !scene.is_game());
+                self.leave();
+            }
+            Transition::Quit => self.scenes.clear(),
+        }
+    }
+    /// Simulates a frame of the scene on top (online, the games go on whatever it is).
+    pub(crate) fn frame(&mut self) {
+        match self.scenes.last_mut() {
+            Some(Scene::Countdown(frames)) => {
+                *frames -= 1;
+                if *frames == 0 {
+                    *self.scenes.last_mut().unwrap() = Scene::Playing;
+                    self.players.iter_mut().for_each(|player| player.game.start());
+                }
+            }
+            Some(Scene::Playing) if self.session.is_none() => self.step(),
+            Some(Scene::Replay { replay, next }) => {
+                let game = &mut self.players[0].game;
+                replay.play(game, next);
+                game.step();
+            }
+            _ => {}
+        }
+    }
+    /// Shows the results once the games are over (saving the record and the replay of a game).
+    pub(crate) fn check_over(&mut self) {
+        if !self.is_over() {
+            return;
+        }
+        match self.scenes.last() {
+            Some(Scene::Playing) => {
+                let ranked = self.finish();
+                self.scenes.push(Scene::Results);
+                if let Some((entry, rank)) = ranked {
+                    self.scenes.push(Scene::NameEntry { entry, rank });
+                }
+            }
+            Some(Scene::Replay { .. }) => self.scenes.push(Scene::Results),
+            _ => {}
+        }
+    }
+    /// Draws the scenes from the last one which isn't an overlay, `width` being the width
+    /// of the window.
+    pub(crate) fn draw_scenes(&mut self, canvas: &mut Canvas<Window>, texts: &mut Texts, atlas: Option<&Atlas>, width: u32) -> Result<(), Box<dyn StdError>> {
+        let first = self.scenes.iter().rposition(|scene| !scene.is_overlay()).unwrap_or(0);
+        let mut scenes = std::mem::take(&mut self.scenes);
+        let result = scenes[first..].iter_mut().try_for_each(|scene| self.draw_scene(scene, canvas, texts, atlas, width));
+        self.scenes = scenes;
+        result
+    }
+    /// Draws `scene`, over the ones below it if it's an overlay.
+    fn draw_scene(&mut self, scene: &mut Scene, canvas: &mut Canvas<Window>, texts: &mut Texts, atlas: Option<&Atlas>, width: u32) -> Result<(), Box<dyn StdError>> {
+        match scene {
+            Scene::Title { selected } => interface::draw_menu(canvas, texts, width, &Menu {
+                title: "RUSTY TETRIS",
+                rows: TITLE_ROWS.iter().map(|row| (row.to_string(), String::new())).collect(),
+                selected: *selected,
+                hint: "ENTER: CHOOSE  ESC: QUIT".to_string(),
+            }),
+            Scene::ModeSelect { selected, level, rows } => interface::draw_menu(canvas, texts, width, &Menu {
+                title: "MODE",
+                rows: MODE_ROWS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| {
+                        let value = match i {
+                            3 | 4 => format!("LEVEL {level}"),
+                            5 | 6 => format!("{rows} ROWS"),
+                            _ => String::new(),
+                        };
+                        (row.to_string(), value)
+                    })
+                    .collect(),
+                selected: *selected,
+                hint: "ENTER: PLAY  ESC: BACK".to_string(),
+            }),
+            Scene::Countdown(frames) => {
+                self.draw_games(canvas, texts, atlas)?;
+                let banner = if *frames > GO { "READY" } else { "GO" };
+                let layout = interface::width(self.config.stats);
+                for i in 0..self.players.len() {
+                    interface::draw_banner(canvas, texts, i, layout, banner)?;
+                }
+                Ok(())
+            }
+            Scene::Playing | Scene::Replay { .. } => self.draw_games(canvas, texts, atlas),
+            Scene::Paused { selected, rows } => interface::draw_menu(canvas, texts, width, &Menu {
+                title: "PAUSED",
+                rows: rows.iter().map(|row| (row.to_string(), String::new())).collect(),
+                selected: *selected,
+                hint: "ENTER: CHOOSE  ESC: RESUME".to_string(),
+            }),
+            Scene::Results => self.draw_results(canvas, texts),
+            Scene::Interrupted(err) => interface::draw_menu(canvas, texts, width, &Menu {
+                title: match err {
+                    net::Error::Disconnected => "DISCONNECTED",
+                    net::Error::Desync(_) => "DESYNC",
+                    _ => "CONNECTION LOST",
+                },
+                rows: vec![(err.to_string().to_uppercase(), String::new())],
+                selected: 0,
+                hint: "ENTER: MENU".to_string(),
+            }),
+            Scene::Settings(settings) => settings.draw(canvas, texts, &self.theme, width),
+            Scene::Replays { selected, saved } => interface::draw_menu(canvas, texts, width, &Menu {
+                title: "REPLAYS",
+                rows: saved
+                    .iter()
+                    .map(|saved| {
+                        let replay = &saved.replay;
+                        let value = match replay.mode {
+                            Mode::Sprint | Mode::Dig { .. } | Mode::Finesse => interface::format_time(Game::FRAME * replay.frames),
+                            _ => replay.score.to_string(),
+                        };
+                        (replay.mode.to_string().to_uppercase(), value)
+                    })
+                    .collect(),
+                selected: *selected,
+                hint: match saved.get(*selected) {
+                    Some(saved) => format!("{}  ENTER: WATCH", replays::format_date(saved.time)),
+                    None => "NO REPLAYS YET  ESC: BACK".to_string(),
+                },
+            }),
+            Scene::NameEntry { entry, rank } => interface::draw_menu(canvas, texts, width, &Menu {
+                title: &format!("NEW RECORD #{}", *rank + 1),
+                rows: vec![
+                    ("NAME".to_string(), format!("{}_", entry.name)),
+                    (leaderboards::title(entry.mode, entry.delays), entry.result()),
+                ],
+                selected: 0,
+                hint: "ENTER: SAVE  ESC: SKIP".to_string(),
+            }),
+            Scene::Leaderboards { board, selected, entries } => {
+                let Some(&(mode, delays)) = leaderboards::boards(entries).get(*board) else {
+                    return interface::draw_menu(canvas, texts, width, &Menu {
+                        title: "LEADERBOARDS",
+                        rows: Vec::new(),
+                        selected: 0,
+                        hint: "NO RECORDS YET  ESC: BACK".to_string(),
+                    });
+                };
+                let shown = leaderboards::board(entries, mode, delays);
+                interface::draw_menu(canvas, texts, width, &Menu {
+                    title: &format!("< {} >", leaderboards::title(mode, delays)),
+                    rows: shown
+                        .iter()
+                        .enumerate()
+                        .map(|(i, entry)| (format!("{}. {}", i + 1, entry.name), entry.result()))
+                        .collect(),
+                    selected: *selected,
+                    hint: match shown.get(*selected) {
+                        Some(entry) => format!("{}  {} LINES", replays::format_date(entry.date), entry.lines),
+                        None => String::new(),
+                    },
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    interface,
+    records,
+    skin::{Atlas, Blocks, Skin},
+    text::Texts,
+};
+use config::{Config, Theme};
+use game::Game;
+use std::{
+    error::Error as StdError,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use sdl2::{
+    image::SaveSurface,
+    pixels::{Color, PixelFormatEnum},
+    rect::Rect,
+    render::{BlendMode, Canvas},
+    surface::Surface,
+};
+
+/// Draws `games` side by side as they are in the window (without the menus and the results),
+/// with the settings of `config` and the colors of `theme`, at the size of the layout
+/// (`interface::HEIGHT` pixels high).
+///
+/// They are drawn on a software surface, which needs neither a window nor a GPU.
+/// # Returns
+/// an error if the font, the skin or the block textures can't be loaded.
+pub fn render(games: &[&Game], config: &Config, theme: &Theme) -> Result<Surface<'static>, Box<dyn StdError>> {
+    let skin = Skin::from_name(&config.skin).ok_or_else(|| format!("unknown skin {:?}", config.skin))?;
+    let width = interface::width(config.stats);
+    let surface = Surface::new(width * games.len().max(1) as u32, interface::HEIGHT, PixelFormatEnum::RGBA32)?;
+    let mut canvas = Canvas::from_surface(surface)?;
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::from(theme.background.window));
+    canvas.clear();
+    {
+        // the textures are dropped before the canvas gives its surface back
+        let ttf = sdl2::ttf::init()?;
+        let creator = canvas.texture_creator();
+        let mut texts = Texts::new(&ttf, &creator, config.font.as_deref())?;
+        texts.set_color(theme.hud.text.into());
+        let atlas = match &theme.atlas {
+            Some(path) => Some(Atlas::load(&creator, path, theme.ghost.alpha)?),
+            None => None,
+        };
+        let blocks = Blocks {
+            skin,
+            theme,
+            atlas: atlas.as_ref(),
+            colors: config.palette.colors(theme),
+            patterns: config.patterns,
+        };
+        for (i, game) in games.iter().enumerate() {
+            canvas.set_viewport(Rect::new(i as i32 * width as i32, 0, width, interface::HEIGHT));
+            // drawing may refill the bag of the game
+            interface::draw(&mut canvas, &mut texts, &mut (*game).clone(), None, config, &blocks)?;
+        }
+    }
+    Ok(canvas.into_surface())
+}
+
+/// Saves an image of `games` (see `render`) as a PNG at `path`.
+pub fn save(games: &[&Game], config: &Config, theme: &Theme, path: &Path) -> Result<(), Box<dyn StdError>> {
+    render(games, config, theme)?.save(path)?;
+    Ok(())
+}
+
+/// Saves an image of `games` as `<mode>-<time>.png` in the `screenshots` folder of the data
+/// directory.
+/// # Returns
+/// the path of the image.
+pub(crate) fn take(games: &[&Game], config: &Config, theme: &Theme) -> Result<PathBuf, Box<dyn StdError>> {
+    let Some(dir) = records::data_dir().map(|dir| dir.join("screenshots")) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory").into());
+    };
+    fs::create_dir_all(&dir)?;
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mode = games.first().map_or("title", |game| game.mode.name());
+    let path = dir.join(format!("{mode}-{}.png", time.as_millis()));
+    save(games, config, theme, &path)?;
+    Ok(path)
+}
@@ -0,0 +1,348 @@
+use crate::{
+    interface::{self, text},
+    skin::{self, Skin},
+    text::Texts,
+};
+use config::{Config, Handling, Keys, Palette, Theme, MAX_DELAY, MAX_PREVIEW, MAX_VOLUME, RESERVED_KEYS};
+use game::{Action, Mode};
+use matrix::Links;
+use pieces::TetrominoKind as Kind;
+use std::{
+    error::Error as StdError,
+    time::{Duration, Instant},
+};
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    rect::Rect,
+    render::Canvas,
+    video::Window,
+};
+
+const MARGIN: i32 = 20;
+/// Height of a row of text.
+const ROW: i32 = 48;
+/// Column of the values.
+const VALUE: i32 = 420;
+/// Column of the previews.
+const PREVIEW: i32 = 640;
+/// Size of the cells of the previews.
+const PREVIEW_CELL: u32 = 28;
+/// Cells of the previews.
+const PREVIEW_CELLS: u32 = 10;
+/// Time the previews stay at their end before starting again.
+const PREVIEW_PAUSE: Duration = Duration::from_millis(500);
+const DAS_STEP: u64 = 10;
+const ARR_STEP: u64 = 5;
+/// Step of the volumes, in percent.
+const VOLUME_STEP: u64 = 10;
+/// Biggest soft drop factor the settings go to (the config accepts any).
+const MAX_SDF: u32 = 40;
+/// Names of the tables of `config::Bindings`.
+const SECTIONS: [&str; 3] = ["single", "left", "right"];
+
+/// A row of the settings.
+#[derive(Clone, Copy, PartialEq)]
+enum Item {
+    /// Which bindings are edited.
+    Section,
+    Key(Action),
+    Das,
+    Arr,
+    Sdf,
+    Preview,
+    Ghost,
+    Grid,
+    Skin,
+    Palette,
+    Patterns,
+    /// Volume of every sound.
+    Master,
+    /// Volume of the sound effects.
+    Effects,
+    /// Volume of the music.
+    Music,
+    Save,
+}
+
+const ITEMS: [Item; 20] = [
+    Item::Section,
+    Item::Key(Action::MoveLeft),
+    Item::Key(Action::MoveRight),
+    Item::Key(Action::SoftDrop),
+    Item::Key(Action::HardDrop),
+    Item::Key(Action::RotateClockwise),
+    Item::Key(Action::RotateCounterClockwise),
+    Item::Das,
+    Item::Arr,
+    Item::Sdf,
+    Item::Preview,
+    Item::Ghost,
+    Item::Grid,
+    Item::Skin,
+    Item::Palette,
+    Item::Patterns,
+    Item::Master,
+    Item::Effects,
+    Item::Music,
+    Item::Save,
+];
+
+/// What a key press did in the settings.
+pub(crate) enum Response {
+    None,
+    /// The `config` changed and should be applied.
+    Changed,
+    /// The settings were closed.
+    Close,
+}
+
+/// The settings screen, which edits a copy of the config.
+pub(crate) struct Settings {
+    pub(crate) config: Config,
+    /// Index of the selected row in `ITEMS`.
+    selected: usize,
+    /// Index of the bindings edited in `SECTIONS`.
+    section: usize,
+    /// `true` while waiting for the key to bind to the selected action.
+    rebinding: bool,
+    /// Result of the last save or rebinding.
+    message: Option<String>,
+    /// Time the settings were opened at, which the previews are animated from.
+    opened: Instant,
+}
+
+impl Settings {
+    /// Opens the settings, editing the keys of the bindings at `section` (see `SECTIONS`) first.
+    pub(crate) fn new(config: Config, section: usize) -> Self {
+        Self {
+            config,
+            selected: 0,
+            section,
+            rebinding: false,
+            message: None,
+            opened: Instant::now(),
+        }
+    }
+    /// Returns the bindings edited.
+    fn keys(&mut self) -> &mut Keys {
+        let bindings = &mut self.config.bindings;
+        match self.section {
+            0 => &mut bindings.single,
+            1 => &mut bindings.left,
+            _ => &mut bindings.right,
+        }
+    }
+    /// Handles a press of `key` (`repeat` if it is held).
+    pub(crate) fn key(&mut self, key: Keycode, repeat: bool) -> Response {
+        if self.rebinding {
+            return if repeat { Response::None } else { self.rebind(key) };
+        }
+        let item = ITEMS[self.selected];
+        match key {
+            Keycode::Escape | Keycode::Tab => return Response::Close,
+            Keycode::Up => self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len(),
+            Keycode::Down => self.selected = (self.selected + 1) % ITEMS.len(),
+            Keycode::Left => return self.adjust(item, false),
+            Keycode::Right => return self.adjust(item, true),
+            Keycode::Return if !repeat => match item {
+                Item::Key(_) => {
+                    self.rebinding = true;
+                    self.message = None;
+                }
+                Item::Save => {
+                    self.message = Some(match self.config.save() {
+                        Ok(()) => "SAVED".to_string(),
+                        Err(err) => {
+                            eprintln!("Cannot save the config: {err}");
+                            "CANNOT SAVE".to_string()
+                        }
+                    });
+                }
+                _ => return self.adjust(item, true),
+            },
+            _ => {}
+        }
+        Response::None
+    }
+    /// Binds `key` to the selected action instead of its keys (and unbinds it from the others).
+    fn rebind(&mut self, key: Keycode) -> Response {
+        self.rebinding = false;
+        let Item::Key(action) = ITEMS[self.selected] else { return Response::None };
+        if key == Keycode::Escape {
+            return Response::None;
+        }
+        let name = key.name();
+        if RESERVED_KEYS.iter().any(|reserved| reserved.eq_ignore_ascii_case(&name)) {
+            self.message = Some(format!("{} IS RESERVED", name.to_uppercase()));
+            return Response::None;
+        }
+        let keys = self.keys();
+        for other in Action::ALL {
+            keys.get_mut(other).retain(|bound| !bound.eq_ignore_ascii_case(&name));
+        }
+        *keys.get_mut(action) = vec![name];
+        Response::Changed
+    }
+    /// Increases (`up`) or decreases the value of `item`.
+    fn adjust(&mut self, item: Item, up: bool) -> Response {
+        let handling = &mut self.config.handling;
+        let volume = &mut self.config.volume;
+        match item {
+            Item::Section => {
+                self.section = (self.section + if up { 1 } else { SECTIONS.len() - 1 }) % SECTIONS.len();
+                return Response::None;
+            }
+            Item::Key(_) | Item::Save => return Response::None,
+            Item::Das => handling.das = step(handling.das, DAS_STEP, up).min(MAX_DELAY),
+            Item::Arr => handling.arr = step(handling.arr, ARR_STEP, up).min(MAX_DELAY),
+            Item::Sdf => handling.sdf = step(handling.sdf.into(), 1, up).min(MAX_SDF.into()) as u32,
+            Item::Preview => self.config.preview = step(self.config.preview as u64, 1, up).min(MAX_PREVIEW as u64) as usize,
+            Item::Ghost => self.config.ghost = !self.config.ghost,
+            Item::Grid => self.config.grid = !self.config.grid,
+            Item::Skin => {
+                let i = Skin::ALL.iter().position(|skin| skin.name() == self.config.skin).unwrap_or(0);
+                let i = (i + if up { 1 } else { Skin::ALL.len() - 1 }) % Skin::ALL.len();
+                self.config.skin = Skin::ALL[i].name().to_string();
+            }
+            Item::Palette => {
+                let i = Palette::ALL.iter().position(|palette| *palette == self.config.palette).unwrap_or(0);
+                self.config.palette = Palette::ALL[(i + if up { 1 } else { Palette::ALL.len() - 1 }) % Palette::ALL.len()];
+            }
+            Item::Patterns => self.config.patterns = !self.config.patterns,
+            Item::Master => volume.master = step(volume.master.into(), VOLUME_STEP, up).min(MAX_VOLUME.into()) as u32,
+            Item::Effects => volume.effects = step(volume.effects.into(), VOLUME_STEP, up).min(MAX_VOLUME.into()) as u32,
+            Item::Music => volume.music = step(volume.music.into(), VOLUME_STEP, up).min(MAX_VOLUME.into()) as u32,
+        }
+        self.message = None;
+        Response::Changed
+    }
+    /// Draws the settings over the whole window, `width` wide.
+    pub(crate) fn draw(&mut self, canvas: &mut Canvas<Window>, texts: &mut Texts, theme: &Theme, width: u32) -> Result<(), Box<dyn StdError>> {
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 230));
+        canvas.fill_rect(Rect::new(0, 0, width, interface::HEIGHT))?;
+
+        text(canvas, texts, MARGIN * 2, MARGIN, "SETTINGS")?;
+
+        for (i, item) in ITEMS.into_iter().enumerate() {
+            let y = MARGIN + ROW * (i as i32 + 1);
+            if i == self.selected {
+                text(canvas, texts, MARGIN, y, ">")?;
+            }
+            let (label, value) = self.row(item, i == self.selected);
+            text(canvas, texts, MARGIN * 3, y, label)?;
+            text(canvas, texts, VALUE, y, &value)?;
+        }
+
+        // preview of the selected setting
+        let y = MARGIN + ROW * (self.selected as i32 + 1) + (ROW - PREVIEW_CELL as i32) / 2;
+        let elapsed = self.opened.elapsed();
+        let skin = Skin::from_name(&self.config.skin).unwrap_or_default();
+        let colors = self.config.palette.colors(theme);
+        match ITEMS[self.selected] {
+            Item::Das | Item::Arr => draw_strip(canvas, y, shift_preview(self.config.handling, elapsed), skin, theme, colors.t.into())?,
+            Item::Sdf => draw_strip(canvas, y, soft_drop_preview(self.config.handling, elapsed), skin, theme, colors.t.into())?,
+            Item::Skin | Item::Palette | Item::Patterns => {
+                let kinds = [Kind::I, Kind::O, Kind::T, Kind::S, Kind::Z, Kind::J, Kind::L];
+                for (i, kind) in kinds.into_iter().enumerate() {
+                    let rect = Rect::new(PREVIEW + i as i32 * PREVIEW_CELL as i32, y, PREVIEW_CELL, PREVIEW_CELL);
+                    skin.draw_block(canvas, rect, colors.piece(kind).into(), Links::default())?;
+                    if self.config.patterns {
+                        skin::draw_pattern(canvas, rect, kind)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let hint = match &self.message {
+            Some(message) => message.as_str(),
+            None if self.rebinding => "ESC: CANCEL",
+            None => "ENTER: CHANGE  ESC: BACK",
+        };
+        text(canvas, texts, MARGIN * 2, MARGIN + ROW * (ITEMS.len() as i32 + 1), hint)?;
+        Ok(())
+    }
+    /// Returns the label and the value shown on the row of `item`.
+    fn row(&mut self, item: Item, selected: bool) -> (&'static str, String) {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" }.to_string();
+        let handling = self.config.handling;
+        match item {
+            Item::Section => ("KEYS OF", format!("< {} >", SECTIONS[self.section].to_uppercase())),
+            Item::Key(action) => {
+                let value = if selected && self.rebinding {
+                    "PRESS A KEY".to_string()
+                } else {
+                    self.keys().get_mut(action).join(", ").to_uppercase()
+                };
+                let label = match action {
+                    Action::MoveLeft => "MOVE LEFT",
+                    Action::MoveRight => "MOVE RIGHT",
+                    Action::SoftDrop => "SOFT DROP",
+                    Action::HardDrop => "HARD DROP",
+                    Action::RotateClockwise => "ROTATE CW",
+                    Action::RotateCounterClockwise => "ROTATE CCW",
+                };
+                (label, value)
+            }
+            Item::Das => ("DAS", format!("{} MS", handling.das)),
+            Item::Arr => ("ARR", format!("{} MS", handling.arr)),
+            Item::Sdf if handling.sdf == 0 => ("SDF", "INSTANT".to_string()),
+            Item::Sdf => ("SDF", format!("{}X", handling.sdf)),
+            Item::Preview => ("NEXT PIECES", self.config.preview.to_string()),
+            Item::Ghost => ("GHOST", on_off(self.config.ghost)),
+            Item::Grid => ("GRID", on_off(self.config.grid)),
+            Item::Skin => ("SKIN", self.config.skin.to_uppercase()),
+            Item::Palette => ("COLORS", self.config.palette.name().replace('-', " ").to_uppercase()),
+            Item::Patterns => ("PATTERNS", on_off(self.config.patterns)),
+            Item::Master => ("VOLUME", format!("{}%", self.config.volume.master)),
+            Item::Effects => ("SOUND", format!("{}%", self.config.volume.effects)),
+            Item::Music => ("MUSIC", format!("{}%", self.config.volume.music)),
+            Item::Save => ("SAVE", String::new()),
+        }
+    }
+}
+
+/// Returns `value` plus (`up`) or minus `step`, not below 0.
+fn step(value: u64, step: u64, up: bool) -> u64 {
+    if up { value + step } else { value.saturating_sub(step) }
+}
+
+/// Returns the cell of a piece whose move key is held since `elapsed` (looping), with `handling`.
+fn shift_preview(handling: Handling, elapsed: Duration) -> u32 {
+    let handling = game::Handling::from(handling);
+    let last = PREVIEW_CELLS - 1;
+    // the press moves the piece at once, then it waits for the DAS and shifts every ARR
+    let cycle = handling.das + handling.arr * (last - 1) + PREVIEW_PAUSE;
+    let held = Duration::from_nanos((elapsed.as_nanos() % cycle.as_nanos()) as u64);
+    if held < handling.das {
+        1
+    } else if handling.arr.is_zero() {
+        last
+    } else {
+        (2 + ((held - handling.das).as_nanos() / handling.arr.as_nanos()) as u32).min(last)
+    }
+}
+
+/// Returns the cell of a piece soft dropped since `elapsed` (looping) at the first level's
+/// gravity, with `handling`.
+fn soft_drop_preview(handling: Handling, elapsed: Duration) -> u32 {
+    let last = PREVIEW_CELLS - 1;
+    if handling.sdf == 0 {
+        return last;
+    }
+    let tick = Mode::Endless.gravity(1) / handling.sdf;
+    let cycle = tick * last + PREVIEW_PAUSE;
+    let dropped = elapsed.as_nanos() % cycle.as_nanos();
+    ((dropped / tick.as_nanos()) as u32).min(last)
+}
+
+/// Draws a row of `PREVIEW_CELLS` cells at `y` on the background of `theme`, with a block of `color`
+/// in the cell `at`.
+fn draw_strip(canvas: &mut Canvas<Window>, y: i32, at: u32, skin: Skin, theme: &Theme, color: Color) -> Result<(), String> {
+    let (r, g, b) = theme.background.matrix;
+    canvas.set_draw_color(Color::RGB(r, g, b));
+    canvas.fill_rect(Rect::new(PREVIEW, y, PREVIEW_CELL * PREVIEW_CELLS, PREVIEW_CELL))?;
+    let rect = Rect::new(PREVIEW + (at * PREVIEW_CELL) as i32, y, PREVIEW_CELL, PREVIEW_CELL);
+    skin.draw_block(canvas, rect, color, Links::default())
+}
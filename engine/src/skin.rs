@@ -0,0 +1,209 @@
+use config::{Theme, theme::{Colors, GhostStyle}};
+use matrix::{Block, Links};
+use pieces::TetrominoKind as Kind;
+use std::path::Path;
+use sdl2::{
+    image::LoadTexture,
+    pixels::Color,
+    rect::Rect,
+    render::{Canvas, RenderTarget, Texture, TextureCreator, TextureQuery},
+};
+
+/// Rows of tiles of an atlas, one for each block (see `Theme::atlas`).
+const ATLAS_ROWS: u32 = 8;
+/// Tiles of a row of an atlas of connected blocks, one for each `Links`.
+const CONNECTED_TILES: u32 = 16;
+
+/// How the blocks are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Skin {
+    /// Flat squares.
+    #[default]
+    Classic,
+    /// Squares lit from the top left.
+    Bevel,
+    /// Dim squares with a bright border, around the tetromino they belong to.
+    Outline,
+}
+
+impl Skin {
+    /// Every skin, in the order the settings cycle through them.
+    pub(crate) const ALL: [Self; 3] = [Self::Classic, Self::Bevel, Self::Outline];
+
+    /// Returns the name of the skin in the config.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Bevel => "bevel",
+            Self::Outline => "outline",
+        }
+    }
+    /// Returns the skin called `name`, `None` if there isn't one.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|skin| skin.name() == name)
+    }
+    /// Draws a block of `color` filling `rect`, joined on the sides of `links` to the other blocks
+    /// of its tetromino.
+    pub(crate) fn draw_block(self, canvas: &mut Canvas<impl RenderTarget>, rect: Rect, color: Color, links: Links) -> Result<(), String> {
+        // width of the edges
+        let edge = (rect.width() / 8).max(1);
+        match self {
+            Self::Classic => {
+                canvas.set_draw_color(color);
+                canvas.fill_rect(rect)
+            }
+            Self::Bevel => {
+                canvas.set_draw_color(shade(color, 0.6));
+                canvas.fill_rect(rect)?;
+                canvas.set_draw_color(shade(color, 1.4));
+                canvas.fill_rect(Rect::new(rect.x(), rect.y(), rect.width() - edge, rect.height() - edge))?;
+                canvas.set_draw_color(color);
+                canvas.fill_rect(Rect::new(
+                    rect.x() + edge as i32,
+                    rect.y() + edge as i32,
+                    rect.width().saturating_sub(edge * 2),
+                    rect.height().saturating_sub(edge * 2),
+                ))
+            }
+            Self::Outline => {
+                canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, (color.a as u32 * 60 / 255) as u8));
+                canvas.fill_rect(rect)?;
+                canvas.set_draw_color(color);
+                // no border between the blocks of a tetromino
+                let edges: Vec<_> = edges(rect, edge)
+                    .into_iter()
+                    .filter(|(side, _)| links.bits() & side.bits() == 0)
+                    .map(|(_, edge)| edge)
+                    .collect();
+                canvas.fill_rects(&edges)
+            }
+        }
+    }
+}
+
+/// Returns the edges of `rect`, `width` wide, with their side.
+fn edges(rect: Rect, width: u32) -> [(Links, Rect); 4] {
+    [
+        (Links::UP, Rect::new(rect.x(), rect.y(), rect.width(), width)),
+        (Links::DOWN, Rect::new(rect.x(), rect.bottom() - width as i32, rect.width(), width)),
+        (Links::LEFT, Rect::new(rect.x(), rect.y(), width, rect.height())),
+        (Links::RIGHT, Rect::new(rect.right() - width as i32, rect.y(), width, rect.height())),
+    ]
+}
+
+/// The block textures of a theme (see `Theme::atlas`).
+pub(crate) struct Atlas<'a> {
+    texture: Texture<'a>,
+    /// The same textures with the opacity of the ghost.
+    ghost: Texture<'a>,
+    /// Size of the tiles.
+    tile: u32,
+    /// `true` if a row has a tile for each `Links`.
+    connected: bool,
+}
+
+impl<'a> Atlas<'a> {
+    /// Loads the atlas at `path`, its ghost with an opacity of `ghost_alpha`.
+    /// # Returns
+    /// an error if the image can't be read or doesn't have the layout of an atlas.
+    pub(crate) fn load<C>(creator: &'a TextureCreator<C>, path: &Path, ghost_alpha: u8) -> Result<Self, String> {
+        let texture = creator.load_texture(path)?;
+        let mut ghost = creator.load_texture(path)?;
+        ghost.set_alpha_mod(ghost_alpha);
+        let TextureQuery { width, height, .. } = texture.query();
+        let tile = height / ATLAS_ROWS;
+        if tile == 0 || height % ATLAS_ROWS != 0 || (width != tile && width != tile * CONNECTED_TILES) {
+            return Err(format!(
+                "invalid block textures {}: {width}x{height} (expected {ATLAS_ROWS} rows of 1 or {CONNECTED_TILES} square tiles)",
+                path.display(),
+            ));
+        }
+        Ok(Self {
+            texture,
+            ghost,
+            tile,
+            connected: width != tile,
+        })
+    }
+    /// Draws the tile of `block` filling `rect` (its `ghost` if `true`).
+    fn draw(&self, canvas: &mut Canvas<impl RenderTarget>, rect: Rect, block: Block, ghost: bool) -> Result<(), String> {
+        let row = match block {
+            Block::Piece(kind, _) => kind.index() as u32,
+            Block::Garbage => ATLAS_ROWS - 1,
+        };
+        let column = if self.connected { block.links().bits() as u32 } else { 0 };
+        let source = Rect::new((column * self.tile) as i32, (row * self.tile) as i32, self.tile, self.tile);
+        canvas.copy(if ghost { &self.ghost } else { &self.texture }, source, rect)
+    }
+}
+
+/// Draws the blocks: with the textures of the theme if it has some, with the skin in the colors
+/// of the palette otherwise.
+pub(crate) struct Blocks<'t, 'a> {
+    pub(crate) skin: Skin,
+    pub(crate) theme: &'t Theme,
+    pub(crate) atlas: Option<&'t Atlas<'a>>,
+    /// Colors of the palette of the config (see `Palette::colors`).
+    pub(crate) colors: Colors,
+    /// If `true` the pattern of its kind is drawn on each block of a tetromino.
+    pub(crate) patterns: bool,
+}
+
+impl Blocks<'_, '_> {
+    /// Draws `block` filling `rect`.
+    pub(crate) fn draw(&self, canvas: &mut Canvas<impl RenderTarget>, rect: Rect, block: Block) -> Result<(), String> {
+        match self.atlas {
+            Some(atlas) => atlas.draw(canvas, rect, block, false)?,
+            None => self.skin.draw_block(canvas, rect, self.colors.block(block).into(), block.links())?,
+        }
+        match block {
+            Block::Piece(kind, _) if self.patterns => draw_pattern(canvas, rect, kind),
+            _ => Ok(()),
+        }
+    }
+    /// Draws the ghost of `block` filling `rect`, in the style of the theme.
+    pub(crate) fn draw_ghost(&self, canvas: &mut Canvas<impl RenderTarget>, rect: Rect, block: Block) -> Result<(), String> {
+        let (r, g, b) = self.colors.block(block);
+        let color = Color::RGBA(r, g, b, self.theme.ghost.alpha);
+        match (self.theme.ghost.style, self.atlas) {
+            (GhostStyle::Transparent, _) => {
+                canvas.set_draw_color(color);
+                canvas.fill_rect(rect)
+            }
+            (GhostStyle::Outline, _) => {
+                canvas.set_draw_color(color);
+                canvas.fill_rects(&edges(rect, (rect.width() / 8).max(1)).map(|(_, edge)| edge))
+            }
+            (GhostStyle::Block, Some(atlas)) => atlas.draw(canvas, rect, block, true),
+            (GhostStyle::Block, None) => self.skin.draw_block(canvas, rect, color, block.links()),
+        }
+    }
+}
+
+/// Returns `color` multiplied by `factor` (lighter above 1, darker below).
+fn shade(color: Color, factor: f32) -> Color {
+    let channel = |c: u8| (c as f32 * factor).min(255.0) as u8;
+    Color::RGBA(channel(color.r), channel(color.g), channel(color.b), color.a)
+}
+
+/// Draws over `rect` the pattern of the tetrominos of `kind`, a dark shape distinct for each kind:
+/// a horizontal bar for I, a vertical one for J, a plus for L, a square for O, a rising diagonal
+/// for S, a falling one for Z and a dot for T.
+pub(crate) fn draw_pattern(canvas: &mut Canvas<impl RenderTarget>, rect: Rect, kind: Kind) -> Result<(), String> {
+    // the block is divided in 8x8 units
+    let unit = (rect.width() / 8).max(1);
+    let at = |x: u32, y: u32, width: u32, height: u32| {
+        Rect::new(rect.x() + (x * unit) as i32, rect.y() + (y * unit) as i32, width * unit, height * unit)
+    };
+    let shape = match kind {
+        Kind::I => vec![at(2, 3, 4, 2)],
+        Kind::J => vec![at(3, 2, 2, 4)],
+        Kind::L => vec![at(2, 3, 4, 2), at(3, 2, 2, 4)],
+        Kind::O => vec![at(2, 2, 4, 1), at(2, 5, 4, 1), at(2, 3, 1, 2), at(5, 3, 1, 2)],
+        Kind::S => vec![at(2, 5, 1, 1), at(3, 4, 1, 1), at(4, 3, 1, 1), at(5, 2, 1, 1)],
+        Kind::Z => vec![at(2, 2, 1, 1), at(3, 3, 1, 1), at(4, 4, 1, 1), at(5, 5, 1, 1)],
+        Kind::T => vec![at(3, 3, 2, 2)],
+    };
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 150));
+    canvas.fill_rects(&shape)
+}
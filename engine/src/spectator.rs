@@ -0,0 +1,280 @@
+use game::{Game, Outcome};
+use matrix::Block;
+use pieces::Tetromino;
+use std::{
+    io::{self, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+/// Bytes queued for a client at most: one which falls further behind is dropped.
+const MAX_UNSENT: usize = 1 << 20;
+
+/// What was last seen of a game, to find what changed since.
+#[derive(Default)]
+struct Seen {
+    /// Cells of the `cur_tetromino`.
+    current: Option<[(isize, isize); 4]>,
+    pieces: u32,
+    lines: u32,
+    score: u32,
+    outcome: Option<Outcome>,
+}
+
+/// A spectator connected, whose socket never blocks the game.
+struct Client {
+    stream: TcpStream,
+    /// Bytes which couldn't be sent yet.
+    unsent: Vec<u8>,
+}
+
+impl Client {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(Self { stream, unsent: Vec::new() })
+    }
+    /// Queues `line` and sends as much as possible of the queued bytes.
+    /// # Returns
+    /// `false` if the client disconnected or can't keep up (see `flush`).
+    fn send(&mut self, line: &str) -> bool {
+        self.unsent.extend_from_slice(line.as_bytes());
+        self.unsent.push(b'\n');
+        self.flush()
+    }
+    /// Sends as much as possible of the queued bytes without blocking.
+    /// # Returns
+    /// `false` if the client disconnected or more than `MAX_UNSENT` bytes are still queued.
+    fn flush(&mut self) -> bool {
+        while !self.unsent.is_empty() {
+            match self.stream.write(&self.unsent) {
+                Ok(0) => return false,
+                Ok(n) => {
+                    self.unsent.drain(..n);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+        self.unsent.len() <= MAX_UNSENT
+    }
+}
+
+/// A local TCP server streaming the events of the games to its clients, one JSON object per line.
+///
+/// Every client first gets a `snapshot` of each game, then the `spawned`, `moved`, `locked`,
+/// `lines_cleared`, `score` and `game_over` events, each with the index of its `player`.
+pub(crate) struct Spectators {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    /// What was last seen of each game.
+    seen: Vec<Seen>,
+}
+
+impl Spectators {
+    /// Listens for spectators on `address`.
+    pub(crate) fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+            seen: Vec::new(),
+        })
+    }
+    /// Sends a snapshot of each game to every client (e.g. after a restart).
+    pub(crate) fn reset<'a>(&mut self, games: impl IntoIterator<Item = &'a Game>) {
+        let games: Vec<_> = games.into_iter().collect();
+        self.seen = games.iter().map(|game| seen(game)).collect();
+        let snapshots: Vec<_> = games.iter().enumerate().map(|(i, game)| snapshot(i, game)).collect();
+        for line in snapshots {
+            self.broadcast(&line);
+        }
+    }
+    /// Accepts the new clients and sends what changed in `games` since the last call.
+    pub(crate) fn update<'a>(&mut self, games: impl IntoIterator<Item = &'a Game>) {
+        let games: Vec<_> = games.into_iter().collect();
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => match Client::new(stream) {
+                    Ok(mut client) => {
+                        if games.iter().enumerate().all(|(i, game)| client.send(&snapshot(i, game))) {
+                            self.clients.push(client);
+                        }
+                    }
+                    Err(err) => eprintln!("Cannot accept a spectator: {err}"),
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    eprintln!("Cannot accept a spectator: {err}");
+                    break;
+                }
+            }
+        }
+        // what couldn't be sent before, even if nothing changed since
+        self.clients.retain_mut(Client::flush);
+        self.seen.resize_with(games.len(), Default::default);
+
+        let mut lines = Vec::new();
+        for (i, (game, seen)) in games.iter().zip(&mut self.seen).enumerate() {
+            let now = self::seen(game);
+            // every piece locked since, with the matrix it locked on
+            for (_, tetromino) in game.history.iter().skip(seen.pieces as usize).take(now.pieces.saturating_sub(seen.pieces) as usize) {
+                lines.push(format!(r#"{{"event":"locked","player":{i},{}}}"#, piece(tetromino)));
+            }
+            if now.lines > seen.lines {
+                lines.push(format!(
+                    r#"{{"event":"lines_cleared","player":{i},"lines":{},"total":{}}}"#,
+                    now.lines - seen.lines,
+                    now.lines,
+                ));
+            }
+            if now.score != seen.score {
+                lines.push(format!(r#"{{"event":"score","player":{i},"score":{}}}"#, now.score));
+            }
+            if let (Some(cur), true) = (&game.cur_tetromino, now.current != seen.current) {
+                let event = if now.pieces == seen.pieces && seen.current.is_some() { "moved" } else { "spawned" };
+                lines.push(format!(r#"{{"event":"{event}","player":{i},{}}}"#, piece(cur)));
+            }
+            if let (Some(outcome), None) = (now.outcome, seen.outcome) {
+                lines.push(format!(
+                    r#"{{"event":"game_over","player":{i},"outcome":"{}","score":{},"lines":{}}}"#,
+                    outcome_name(outcome),
+                    now.score,
+                    now.lines,
+                ));
+            }
+            *seen = now;
+        }
+        for line in lines {
+            self.broadcast(&line);
+        }
+    }
+    /// Sends `line` to every client, dropping the ones which disconnected or can't keep up.
+    fn broadcast(&mut self, line: &str) {
+        self.clients.retain_mut(|client| client.send(line));
+    }
+}
+
+/// Returns what is seen now of `game`.
+fn seen(game: &Game) -> Seen {
+    Seen {
+        current: game.cur_tetromino.as_ref().map(|cur| cur.position().map(|p| (p.x, p.y))),
+        pieces: game.history.len() as u32,
+        lines: game.stats.lines,
+        score: game.score,
+        outcome: game.outcome,
+    }
+}
+
+/// Returns the `snapshot` event of a game: its mode, matrix (one string per row, `.` for an
+/// empty cell, `G` for garbage, the kind of the piece otherwise), current piece and queue.
+fn snapshot(player: usize, game: &Game) -> String {
+    let rows: Vec<String> = game
+        .matrix
+        .iter()
+        .map(|row| {
+            let row: String = row
+                .iter()
+                .map(|block| match block {
+                    None => '.',
+                    Some(Block::Garbage) => 'G',
+                    Some(Block::Piece(kind, _)) => format!("{kind:?}").chars().next().unwrap(),
+                })
+                .collect();
+            format!(r#""{row}""#)
+        })
+        .collect();
+    let current = match &game.cur_tetromino {
+        Some(cur) => format!("{{{}}}", piece(cur)),
+        None => "null".to_string(),
+    };
+    let next: Vec<String> = game.bag.iter().rev().map(|next| format!(r#""{:?}""#, next.kind())).collect();
+    let outcome = match game.outcome {
+        Some(outcome) => format!(r#""{}""#, outcome_name(outcome)),
+        None => "null".to_string(),
+    };
+    format!(
+        r#"{{"event":"snapshot","player":{player},"mode":"{}","matrix":[{}],"current":{current},"next":[{}],"score":{},"lines":{},"level":{},"outcome":{outcome}}}"#,
+        game.mode.name(),
+        rows.join(","),
+        next.join(","),
+        game.score,
+        game.stats.lines,
+        game.level,
+    )
+}
+
+/// Returns the fields of a piece: its kind and its cells (`[x, y]` in the matrix, `y` top-down).
+fn piece(tetromino: &Tetromino) -> String {
+    let cells: Vec<String> = tetromino.position().iter().map(|p| format!("[{},{}]", p.x, p.y)).collect();
+    format!(r#""kind":"{:?}","cells":[{}]"#, tetromino.kind(), cells.join(","))
+}
+
+fn outcome_name(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Completed => "completed",
+        Outcome::ToppedOut => "topped_out",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{Action, Mode};
+    use std::{
+        io::{BufRead, BufReader},
+        thread,
+        time::Duration,
+    };
+
+    /// Returns spectators listening on a free port of localhost, and a client connected to them.
+    fn connect() -> (Spectators, TcpStream) {
+        let spectators = Spectators::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(spectators.listener.local_addr().unwrap()).unwrap();
+        (spectators, client)
+    }
+
+    /// Accepts the pending clients of `spectators`.
+    fn accept(spectators: &mut Spectators, game: &Game) {
+        for _ in 0..100 {
+            spectators.update([game]);
+            if !spectators.clients.is_empty() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("the client isn't accepted");
+    }
+
+    #[test]
+    fn client_gets_a_snapshot_then_the_events() {
+        let (mut spectators, client) = connect();
+        let mut game = Game::with_seed(Mode::Endless, 1);
+        game.step();
+        accept(&mut spectators, &game);
+        game.update(Action::HardDrop);
+        game.step();
+        spectators.update([&game]);
+
+        let mut lines = BufReader::new(client).lines().map(Result::unwrap);
+        let line = lines.next().unwrap();
+        assert!(line.starts_with(r#"{"event":"snapshot","player":0,"mode":"endless""#), "{line}");
+        for event in ["spawned", "locked"] {
+            let line = lines.next().unwrap();
+            assert!(line.starts_with(&format!(r#"{{"event":"{event}","player":0,"kind":"#)), "{line}");
+        }
+    }
+
+    #[test]
+    fn client_which_doesnt_read_is_dropped() {
+        let (mut spectators, _client) = connect();
+        let game = Game::with_seed(Mode::Endless, 1);
+        accept(&mut spectators, &game);
+        // the socket's buffers fill up, then the queue
+        let resets = (0..1_000_000).take_while(|_| {
+            spectators.reset([&game]);
+            !spectators.clients.is_empty()
+        });
+        assert!(resets.count() < 1_000_000, "the client is never dropped");
+    }
+}
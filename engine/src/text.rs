@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    error::Error as StdError,
+    path::Path,
+};
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{
+        Canvas,
+        RenderTarget,
+        Texture,
+        TextureCreator,
+        TextureQuery,
+    },
+    rwops::RWops,
+    ttf::{Font, Sdl2TtfContext},
+    video::WindowContext,
+};
+
+/// Font embedded in the binary (DejaVu Sans, see `assets/LICENSE-DejaVu.txt`).
+const BUNDLED_FONT: &[u8] = include_bytes!("../assets/DejaVuSans.ttf");
+/// Point size of the text.
+const FONT_SIZE: u16 = 50;
+
+/// A rendered string.
+struct Rendered<'a> {
+    texture: Texture<'a>,
+    width: u32,
+    height: u32,
+    /// `true` if it was drawn since the last `sweep`.
+    used: bool,
+}
+
+/// Renders the text with the font, keeping the texture of each string drawn in the last frame
+/// so that only new strings are rendered again.
+pub(crate) struct Texts<'ttf, 'a, C = WindowContext> {
+    font: Font<'ttf, 'static>,
+    /// Creator of the textures, of the canvas they are drawn on.
+    creator: &'a TextureCreator<C>,
+    rendered: HashMap<String, Rendered<'a>>,
+    /// Color the text is drawn in.
+    color: Color,
+}
+
+impl<'ttf, 'a, C> Texts<'ttf, 'a, C> {
+    /// Loads the font at `path`, or the bundled one if `None`.
+    pub(crate) fn new(ttf: &'ttf Sdl2TtfContext, creator: &'a TextureCreator<C>, path: Option<&Path>) -> Result<Self, Box<dyn StdError>> {
+        let font = match path {
+            Some(path) => ttf.load_font(path, FONT_SIZE)?,
+            None => ttf.load_font_from_rwops(RWops::from_bytes(BUNDLED_FONT)?, FONT_SIZE)?,
+        };
+        Ok(Self {
+            font,
+            creator,
+            rendered: HashMap::new(),
+            color: Color::WHITE,
+        })
+    }
+    /// Draws `text` with its top left corner at (`x`, `y`).
+    pub(crate) fn draw(&mut self, canvas: &mut Canvas<impl RenderTarget<Context = C>>, x: i32, y: i32, text: &str) -> Result<(), Box<dyn StdError>> {
+        // SDL_ttf can't render an empty string
+        if text.is_empty() {
+            return Ok(());
+        }
+        if !self.rendered.contains_key(text) {
+            let surface = self.font
+                .render(text)
+                .blended(Color::WHITE)?;
+            let texture = self.creator.create_texture_from_surface(&surface)?;
+            let TextureQuery { width, height, .. } = texture.query();
+            self.rendered.insert(text.to_string(), Rendered { texture, width, height, used: false });
+        }
+        let rendered = self.rendered.get_mut(text).unwrap();
+        rendered.used = true;
+        // rendered white, tinted in the color
+        rendered.texture.set_color_mod(self.color.r, self.color.g, self.color.b);
+        canvas.copy(&rendered.texture, None, Some(Rect::new(x, y, rendered.width, rendered.height)))?;
+        Ok(())
+    }
+    /// Draws the next text in `color`.
+    pub(crate) fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+    /// Returns the width of `text` once drawn.
+    pub(crate) fn width(&self, text: &str) -> Result<u32, Box<dyn StdError>> {
+        if text.is_empty() {
+            return Ok(0);
+        }
+        Ok(self.font.size_of(text)?.0)
+    }
+    /// Drops the textures of the strings not drawn since the last call (once per frame).
+    pub(crate) fn sweep(&mut self) {
+        self.rendered.retain(|_, rendered| std::mem::take(&mut rendered.used));
+    }
+}
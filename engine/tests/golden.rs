@@ -0,0 +1,124 @@
+//! Renders known games without a window and compares them with the reference images of
+//! `tests/golden`, to catch the regressions of the layout of `interface::draw`.
+//!
+//! `GOLDEN_UPDATE=1 cargo test -p engine --test golden` (re)writes the references from the
+//! current drawing: check the images before committing them.
+
+use config::{Config, Theme};
+use engine::screenshot;
+use game::{Action, Game, Mode};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+use sdl2::{
+    image::{LoadSurface, SaveSurface},
+    pixels::PixelFormatEnum,
+    surface::Surface,
+};
+
+/// Largest difference of a channel of a pixel with the reference still counted as the same
+/// (the antialiasing of the text may change a little with the version of FreeType).
+const CHANNEL_TOLERANCE: u8 = 24;
+/// Largest share of the pixels which may differ from the reference.
+const MAX_DIFFERENT: f64 = 0.002;
+/// Seed of the games, for the same pieces on every run.
+const SEED: u64 = 42;
+
+/// Returns the path of the reference image `name`.
+fn reference(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{name}.png"))
+}
+
+/// Does each of `actions` on its own frame, waiting for the next piece after a placement.
+fn play(game: &mut Game, actions: &[Action]) {
+    for &action in actions {
+        while game.cur_tetromino.is_none() && game.outcome.is_none() {
+            game.step();
+        }
+        game.update(action);
+        game.release(action);
+        game.step();
+    }
+}
+
+/// Returns the pixels of `surface` as RGBA, row after row.
+fn pixels(surface: &Surface) -> Vec<u8> {
+    let surface = surface.convert_format(PixelFormatEnum::RGBA32).expect("the surface can be converted");
+    let (width, pitch) = (surface.width() as usize * 4, surface.pitch() as usize);
+    let data = surface.without_lock().expect("a software surface doesn't need a lock");
+    data.chunks(pitch).flat_map(|row| &row[..width]).copied().collect()
+}
+
+/// Renders `game` with `config` and compares it with the reference `name`, or (re)writes the
+/// reference when `GOLDEN_UPDATE` is set.
+/// # Panics
+/// if the image differs from the reference, or the reference doesn't exist.
+fn check(name: &str, game: &Game, config: &Config) {
+    let actual = screenshot::render(&[game], config, &Theme::default()).expect("the game can be rendered");
+    let path = reference(name);
+    if env::var_os("GOLDEN_UPDATE").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        actual.save(&path).expect("the reference can be written");
+        return;
+    }
+    assert!(path.exists(), "{name}: no reference {}, write it with GOLDEN_UPDATE=1", path.display());
+    let expected = Surface::from_file(&path).expect("the reference can be read");
+    assert_eq!(
+        actual.size(),
+        expected.size(),
+        "{name}: the image is {:?}, the reference {:?}",
+        actual.size(),
+        expected.size(),
+    );
+    let different = pixels(&actual)
+        .chunks(4)
+        .zip(pixels(&expected).chunks(4))
+        .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+        .count();
+    let share = different as f64 / (actual.width() * actual.height()) as f64;
+    if share > MAX_DIFFERENT {
+        // kept next to the build to compare them
+        let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.png"));
+        actual.save(&out).expect("the image can be written");
+        panic!("{name}: {different} pixels ({:.2}%) differ from the reference, see {}", share * 100.0, out.display());
+    }
+}
+
+#[test]
+fn empty_board() {
+    check("empty_board", &Game::with_seed(Mode::Endless, SEED), &Config::default());
+}
+
+#[test]
+fn mid_game_with_ghost() {
+    use Action::*;
+    let mut game = Game::with_seed(Mode::Endless, SEED);
+    play(&mut game, &[
+        MoveLeft, MoveLeft, MoveLeft, MoveLeft, HardDrop,
+        MoveRight, MoveRight, MoveRight, MoveRight, HardDrop,
+        RotateClockwise, HardDrop,
+        MoveLeft, MoveLeft, HardDrop,
+        RotateCounterClockwise, MoveRight, MoveRight, HardDrop,
+        HardDrop,
+        RotateClockwise, MoveLeft,
+    ]);
+    let config = Config { ghost: true, ..Config::default() };
+    check("mid_game_with_ghost", &game, &config);
+}
+
+#[test]
+fn full_queue() {
+    let mut game = Game::with_seed(Mode::Sprint, SEED);
+    play(&mut game, &[Action::HardDrop; 3]);
+    let config = Config { preview: config::MAX_PREVIEW, ..Config::default() };
+    check("full_queue", &game, &config);
+}
+
+#[test]
+fn game_over() {
+    let mut game = Game::with_seed(Mode::Endless, SEED);
+    play(&mut game, &[Action::HardDrop; 100]);
+    assert!(game.outcome.is_some(), "stacking 100 pieces in the middle tops out");
+    check("game_over", &game, &Config::default());
+}
@@ -0,0 +1,148 @@
+use crate::Error;
+
+/// Alphabet used by fumen to write each value (6 bits per character).
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Characters a comment can hold, indexed by `char - ' '`.
+const ASCII: &[u8; 95] = b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+/// Each comment value packs 4 characters in base 96.
+const COMMENT_BASE: u32 = 96;
+/// Fumen inserts a `?` every 47 characters (the first chunk shares them with the prefix).
+const CHUNK: usize = 47;
+
+/// Reads the base64 values of a fumen, from first to last.
+pub(crate) struct Reader {
+    values: Vec<u8>,
+    pos: usize,
+}
+
+impl Reader {
+    /// Constructor of `Reader`
+    /// # Args
+    /// `data` is the part of the fumen after the version prefix.
+    pub(crate) fn new(data: &str) -> Result<Self, Error> {
+        let values = data
+            .chars()
+            .filter(|c| *c != '?' && !c.is_whitespace())
+            .map(|c| {
+                BASE64
+                    .iter()
+                    .position(|b| *b as char == c)
+                    .map(|v| v as u8)
+                    .ok_or(Error::InvalidChar(c))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { values, pos: 0 })
+    }
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.values.len()
+    }
+    /// Reads a value written with `n` characters (little endian).
+    pub(crate) fn poll(&mut self, n: usize) -> Result<u32, Error> {
+        let Some(chars) = self.values.get(self.pos..self.pos + n) else {
+            return Err(Error::UnexpectedEnd);
+        };
+        self.pos += n;
+        Ok(chars.iter().rev().fold(0, |acc, v| acc * 64 + *v as u32))
+    }
+}
+
+/// Writes the base64 values of a fumen.
+#[derive(Default)]
+pub(crate) struct Writer {
+    pub(crate) values: Vec<u8>,
+}
+
+impl Writer {
+    /// Writes `value` with `n` characters (little endian).
+    pub(crate) fn push(&mut self, mut value: u32, n: usize) {
+        for _ in 0..n {
+            self.values.push((value % 64) as u8);
+            value /= 64;
+        }
+    }
+    /// Returns the encoded data prefixed by the version.
+    pub(crate) fn finish(self) -> String {
+        let data: Vec<char> = self.values.iter().map(|v| BASE64[*v as usize] as char).collect();
+        let mut out = String::from(crate::PREFIX);
+        let first = data.len().min(CHUNK - crate::PREFIX.len());
+        out.extend(&data[..first]);
+        for chunk in data[first..].chunks(CHUNK) {
+            out.push('?');
+            out.extend(chunk);
+        }
+        out
+    }
+}
+
+/// Reads a comment of `len` characters.
+pub(crate) fn read_comment(reader: &mut Reader, len: usize) -> Result<String, Error> {
+    let mut escaped = String::with_capacity(len);
+    for _ in 0..len.div_ceil(4) {
+        let mut value = reader.poll(5)?;
+        for _ in 0..4 {
+            let index = (value % COMMENT_BASE) as usize;
+            value /= COMMENT_BASE;
+            if escaped.len() < len {
+                escaped.push(*ASCII.get(index).ok_or(Error::InvalidComment)? as char);
+            }
+        }
+    }
+    unescape(&escaped)
+}
+
+/// Writes `comment` preceded by its length.
+pub(crate) fn write_comment(writer: &mut Writer, comment: &str) -> Result<(), Error> {
+    let escaped = escape(comment);
+    if escaped.len() >= 4096 {
+        return Err(Error::CommentTooLong);
+    }
+    writer.push(escaped.len() as u32, 2);
+    for chunk in escaped.as_bytes().chunks(4) {
+        let value = chunk
+            .iter()
+            .rev()
+            .fold(0, |acc, c| acc * COMMENT_BASE + (*c - b' ') as u32);
+        writer.push(value, 5);
+    }
+    Ok(())
+}
+
+/// Same as JavaScript's `escape`, which fumen uses to store comments as ASCII.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for unit in text.encode_utf16() {
+        match char::from_u32(unit as u32) {
+            Some(c) if c.is_ascii_alphanumeric() || "@*_+-./".contains(c) => out.push(c),
+            _ if unit < 0x100 => out.push_str(&format!("%{unit:02X}")),
+            _ => out.push_str(&format!("%u{unit:04X}")),
+        }
+    }
+    out
+}
+
+/// Same as JavaScript's `unescape`.
+fn unescape(text: &str) -> Result<String, Error> {
+    let mut units = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let unit = match rest.strip_prefix("%u") {
+            Some(hex) if hex.len() >= 4 && u16::from_str_radix(&hex[..4], 16).is_ok() => {
+                rest = &hex[4..];
+                u16::from_str_radix(&hex[..4], 16).unwrap()
+            }
+            _ => match rest.strip_prefix('%') {
+                Some(hex) if hex.len() >= 2 && u8::from_str_radix(&hex[..2], 16).is_ok() => {
+                    rest = &hex[2..];
+                    u8::from_str_radix(&hex[..2], 16).unwrap() as u16
+                }
+                _ => {
+                    rest = &rest[c.len_utf8()..];
+                    c as u16
+                }
+            },
+        };
+        units.push(unit);
+    }
+    String::from_utf16(&units).map_err(|_| Error::InvalidComment)
+}
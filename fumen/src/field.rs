@@ -0,0 +1,121 @@
+use matrix::{Block, Links, Matrix};
+use pieces::TetrominoKind as Kind;
+
+/// Width of the fumen's field (same as the `Matrix`).
+pub(crate) const WIDTH: usize = 10;
+/// Rows of the fumen's field, counting the garbage row below the floor.
+pub(crate) const ROWS: usize = 24;
+/// Number of cells of the fumen's field, garbage row included.
+pub(crate) const BLOCKS: usize = WIDTH * ROWS;
+/// Value of a gray (garbage) cell.
+const GRAY: u8 = 8;
+
+/// Fumen's playfield.
+///
+/// Unlike the `Matrix`, rows go **bottom-up**: index `0` is the garbage row below the floor,
+/// index `y + 1` is the row `y` of the field (`0` being the bottom one).
+#[derive(Clone, Default)]
+pub(crate) struct Field([[u8; WIDTH]; ROWS]);
+
+impl Field {
+    /// Returns the value of the `index`-th cell in fumen's order
+    /// (top-left to bottom-right, garbage row last).
+    #[inline]
+    pub(crate) fn get(&self, index: usize) -> u8 {
+        self.0[ROWS - 1 - index / WIDTH][index % WIDTH]
+    }
+    #[inline]
+    pub(crate) fn set(&mut self, index: usize, value: u8) {
+        self.0[ROWS - 1 - index / WIDTH][index % WIDTH] = value;
+    }
+    /// Fills the given cells (`(x, y)`, `y` bottom-up) with a piece.
+    pub(crate) fn fill(&mut self, cells: [(isize, isize); 4], kind: Kind) {
+        for (x, y) in cells {
+            if (0..WIDTH as isize).contains(&x) && (-1..ROWS as isize - 1).contains(&y) {
+                self.0[(y + 1) as usize][x as usize] = value_of(kind);
+            }
+        }
+    }
+    /// Removes the full rows of the field (the garbage row is left untouched).
+    pub(crate) fn clear_lines(&mut self) {
+        let rows: Vec<_> = self.0[1..]
+            .iter()
+            .filter(|row| row.contains(&0))
+            .copied()
+            .collect();
+        let mut field = [[0; WIDTH]; ROWS];
+        field[0] = self.0[0];
+        field[1..=rows.len()].copy_from_slice(&rows);
+        self.0 = field;
+    }
+    /// Pushes the garbage row into the field from the bottom.
+    pub(crate) fn rise(&mut self) {
+        self.0.copy_within(0..ROWS - 1, 1);
+        self.0[0] = [0; WIDTH];
+    }
+    /// Flips the field horizontally (the garbage row is left untouched).
+    pub(crate) fn mirror(&mut self) {
+        for row in &mut self.0[1..] {
+            row.reverse();
+        }
+    }
+    /// Converts the field into a `Matrix`.
+    ///
+    /// Rows above the `Matrix` and the garbage row are cut off.
+    pub(crate) fn to_matrix(&self) -> Matrix {
+        let mut matrix = Matrix::default();
+        for y in 0..Matrix::HEIGHT {
+            for (x, value) in self.0[Matrix::HEIGHT - y].iter().enumerate() {
+                matrix[y][x] = match *value {
+                    GRAY => Some(Block::Garbage),
+                    // fumen doesn't tell which blocks belong to the same piece
+                    value => kind_of(value).map(|kind| Block::Piece(kind, Links::default())),
+                };
+            }
+        }
+        matrix
+    }
+}
+
+impl From<&Matrix> for Field {
+    fn from(matrix: &Matrix) -> Self {
+        let mut field = Self::default();
+        for (y, row) in matrix.iter().enumerate() {
+            for (x, block) in row.iter().enumerate() {
+                field.0[Matrix::HEIGHT - y][x] = match block {
+                    None => 0,
+                    Some(Block::Piece(kind, _)) => value_of(*kind),
+                    Some(Block::Garbage) => GRAY,
+                };
+            }
+        }
+        field
+    }
+}
+
+/// Returns the value fumen uses for each kind of tetromino.
+pub(crate) fn value_of(kind: Kind) -> u8 {
+    match kind {
+        Kind::I => 1,
+        Kind::L => 2,
+        Kind::O => 3,
+        Kind::Z => 4,
+        Kind::T => 5,
+        Kind::J => 6,
+        Kind::S => 7,
+    }
+}
+
+/// Returns the kind of tetromino of a fumen's value, `None` if it isn't a tetromino.
+pub(crate) fn kind_of(value: u8) -> Option<Kind> {
+    Some(match value {
+        1 => Kind::I,
+        2 => Kind::L,
+        3 => Kind::O,
+        4 => Kind::Z,
+        5 => Kind::T,
+        6 => Kind::J,
+        7 => Kind::S,
+        _ => return None,
+    })
+}
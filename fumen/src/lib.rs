@@ -0,0 +1,467 @@
+//! # Fumen
+//! Import and export of fumen (v115) strings, the format used to share boards and setups.
+
+/// Module to read and write fumen's base64 values
+mod codec;
+/// Module to convert fumen's field from/to the `Matrix`
+mod field;
+/// Module to convert fumen's pieces from/to a `Tetromino`
+mod piece;
+
+use codec::{Reader, Writer};
+use field::{BLOCKS, Field};
+use game::{Game, Mode};
+use matrix::Matrix;
+use pieces::{Tetromino, TetrominoKind as Kind};
+use vecmath::Direction;
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+};
+
+/// Prefix of every fumen written.
+const PREFIX: &str = "v115@";
+
+/// Errors which can occur while decoding or encoding a fumen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The string isn't a v115 fumen.
+    UnsupportedVersion,
+    /// The data contains a character which isn't base64.
+    InvalidChar(char),
+    /// The data ends in the middle of a page.
+    UnexpectedEnd,
+    /// A page's field contains an unknown block.
+    InvalidField,
+    /// A comment contains an unknown character.
+    InvalidComment,
+    /// A comment is too long to be written (4095 characters once escaped).
+    CommentTooLong,
+    /// A piece is outside of fumen's field.
+    OutOfField,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion => write!(f, "not a v115 fumen"),
+            Self::InvalidChar(c) => write!(f, "invalid character in fumen: {:?}", c),
+            Self::UnexpectedEnd => write!(f, "fumen ends unexpectedly"),
+            Self::InvalidField => write!(f, "invalid block in fumen's field"),
+            Self::InvalidComment => write!(f, "invalid character in fumen's comment"),
+            Self::CommentTooLong => write!(f, "comment is too long for a fumen"),
+            Self::OutOfField => write!(f, "piece is outside of fumen's field"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// A page of a fumen.
+#[derive(Clone, Default)]
+pub struct Page {
+    /// The field, cut to the size of the `Matrix`.
+    pub matrix: Matrix,
+    /// The piece being played (fumen's operation piece, or the current piece of a quiz).
+    pub current: Option<Tetromino>,
+    /// The piece in hold (from a quiz comment `#Q=[hold](current)queue`).
+    pub hold: Option<Kind>,
+    /// The next pieces, first to last (from a quiz comment).
+    pub queue: Vec<Kind>,
+    /// If `true` the `current` piece is placed and rows are cleared before the next page.
+    pub lock: bool,
+    /// The raw comment of the page.
+    pub comment: String,
+}
+
+impl Page {
+    /// Returns the comment to write: a quiz if there is a hold or a queue, `comment` otherwise.
+    fn encoded_comment(&self) -> String {
+        if self.hold.is_none() && self.queue.is_empty() {
+            return self.comment.clone();
+        }
+        let name = |kind: Option<Kind>| kind.map(piece::kind_char).map(String::from).unwrap_or_default();
+        format!(
+            "#Q=[{}]({}){}",
+            name(self.hold),
+            name(self.current.as_ref().map(Tetromino::kind)),
+            self.queue.iter().copied().map(piece::kind_char).collect::<String>(),
+        )
+    }
+}
+
+impl From<&Game> for Page {
+    /// Takes the board of `game` with its current piece and queue.
+    fn from(game: &Game) -> Self {
+        Self {
+            matrix: game.matrix.clone(),
+            current: game.cur_tetromino.clone(),
+            queue: game.bag.iter().rev().map(Tetromino::kind).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Page> for Game {
+    /// Starts a game from the board, the current piece and the queue of `page`.
+    fn from(page: Page) -> Self {
+        let mut game = Game::new(Mode::default());
+        game.matrix = page.matrix;
+        game.bag = page.queue.into_iter().rev().map(Tetromino::new).collect();
+        if game.bag.is_empty() {
+            game.refill_bag();
+        }
+        game.cur_tetromino = page.current.or_else(|| game.bag.pop());
+        if game.bag.is_empty() {
+            game.refill_bag();
+        }
+        game
+    }
+}
+
+/// Returns every placement of `game` as a page, followed by its current board.
+pub fn game_pages(game: &Game) -> Vec<Page> {
+    game.history
+        .iter()
+        .map(|(matrix, tetromino)| Page {
+            matrix: matrix.clone(),
+            current: Some(tetromino.clone()),
+            lock: true,
+            ..Default::default()
+        })
+        .chain([Page::from(game)])
+        .collect()
+}
+
+/// Decodes each page of a fumen.
+///
+/// The fumen can be given on its own (`v115@...`) or as the URL of a viewer.
+pub fn decode(fumen: &str) -> Result<Vec<Page>, Error> {
+    let data = fumen
+        .match_indices("115@")
+        .find(|(i, _)| fumen[..*i].ends_with(['v', 'm', 'd', 'D']))
+        .map(|(i, prefix)| &fumen[i + prefix.len()..])
+        .ok_or(Error::UnsupportedVersion)?;
+    let mut reader = Reader::new(data)?;
+
+    let mut pages = Vec::new();
+    let mut prev = Field::default();
+    let mut repeat = 0;
+    let mut comment = String::new();
+    while !reader.is_empty() {
+        // field
+        let mut field = prev.clone();
+        if repeat > 0 {
+            repeat -= 1;
+        } else {
+            let mut index = 0;
+            while index < BLOCKS {
+                let value = reader.poll(2)?;
+                let diff = value / BLOCKS as u32;
+                let count = value as usize % BLOCKS + 1;
+                if diff == 8 && count == BLOCKS {
+                    repeat = reader.poll(1)?;
+                }
+                if index + count > BLOCKS {
+                    return Err(Error::InvalidField);
+                }
+                for i in index..index + count {
+                    let block = (field.get(i) as u32 + diff)
+                        .checked_sub(8)
+                        .filter(|block| *block <= 8)
+                        .ok_or(Error::InvalidField)?;
+                    field.set(i, block as u8);
+                }
+                index += count;
+            }
+        }
+
+        // action
+        let mut action = reader.poll(3)?;
+        let kind = field::kind_of((action % 8) as u8);
+        action /= 8;
+        let direction = piece::decode_direction(action % 4);
+        action /= 4;
+        let position = action % BLOCKS as u32;
+        action /= BLOCKS as u32;
+        let [rise, mirror, _color, has_comment, not_lock] = [0; 5].map(|_| {
+            let flag = action % 2 == 1;
+            action /= 2;
+            flag
+        });
+
+        // comment (kept from the previous page if it didn't change)
+        if has_comment {
+            let len = reader.poll(2)? as usize;
+            comment = codec::read_comment(&mut reader, len)?;
+        }
+        let (hold, quiz_current, queue) = piece::parse_quiz(&comment).unwrap_or_default();
+
+        let cells = kind.map(|kind| (kind, piece::decode_cells(kind, direction, position)));
+        pages.push(Page {
+            matrix: field.to_matrix(),
+            current: cells
+                .map(|(kind, cells)| piece::tetromino_at(kind, direction, cells))
+                .or(quiz_current.map(Tetromino::new)),
+            hold,
+            queue,
+            lock: !not_lock,
+            comment: comment.clone(),
+        });
+
+        if !not_lock {
+            if let Some((kind, cells)) = cells {
+                field.fill(cells, kind);
+            }
+            field.clear_lines();
+            if rise {
+                field.rise();
+            }
+            if mirror {
+                field.mirror();
+            }
+        }
+        prev = field;
+    }
+    Ok(pages)
+}
+
+/// Encodes `pages` into a fumen.
+pub fn encode(pages: &[Page]) -> Result<String, Error> {
+    let mut writer = Writer::default();
+    let mut prev = Field::default();
+    // index of the counter of pages with the same field as the previous one
+    let mut repeat: Option<usize> = None;
+    let mut prev_comment = String::new();
+    for (i, page) in pages.iter().enumerate() {
+        // field
+        let mut field = Field::from(&page.matrix);
+        let diffs: Vec<u32> = (0..BLOCKS)
+            .map(|i| field.get(i) as u32 + 8 - prev.get(i) as u32)
+            .collect();
+        if diffs.iter().all(|diff| *diff == 8) {
+            match repeat {
+                Some(at) if writer.values[at] < 63 => writer.values[at] += 1,
+                _ => {
+                    writer.push(8 * BLOCKS as u32 + BLOCKS as u32 - 1, 2);
+                    writer.push(0, 1);
+                    repeat = Some(writer.values.len() - 1);
+                }
+            }
+        } else {
+            repeat = None;
+            for run in diffs.chunk_by(|a, b| a == b) {
+                writer.push(run[0] * BLOCKS as u32 + run.len() as u32 - 1, 2);
+            }
+        }
+
+        // action
+        let (kind, direction, position) = match &page.current {
+            Some(tetromino) => {
+                let (kind, direction) = (tetromino.kind(), tetromino.direction());
+                let cells = piece::cells_of(tetromino);
+                (field::value_of(kind), direction, piece::encode_position(kind, direction, cells)?)
+            }
+            None => (0, Direction::Down, 0),
+        };
+        let comment = page.encoded_comment();
+        let has_comment = comment != prev_comment;
+        // flags from the last read to the first: lock, comment, color, mirror, rise
+        let flags = [!page.lock, has_comment, i == 0, false, false];
+        let mut action = flags.iter().fold(0, |acc, flag| acc * 2 + *flag as u32);
+        action = action * BLOCKS as u32 + position;
+        action = action * 4 + piece::encode_direction(direction);
+        action = action * 8 + kind as u32;
+        writer.push(action, 3);
+
+        if has_comment {
+            codec::write_comment(&mut writer, &comment)?;
+            prev_comment = comment;
+        }
+
+        if page.lock {
+            if let Some(tetromino) = &page.current {
+                field.fill(piece::cells_of(tetromino), tetromino.kind());
+            }
+            field.clear_lines();
+        }
+        prev = field;
+    }
+    Ok(writer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Action;
+    use matrix::Block;
+
+    /// A fumen of 3 pages: a T locked at the bottom with a quiz comment, the field it leaves
+    /// with the same comment, then the same field with another comment.
+    const PAGES: &str = "v115@vhCVQYXAFLDmClcJSAVjrSAVG88AYe88A5tqBAAAeA?AtEAkXprD";
+
+    /// Returns the rows of `matrix` which aren't empty, with their index: `X` for garbage,
+    /// the letter of the piece otherwise.
+    fn rows(matrix: &Matrix) -> Vec<(usize, String)> {
+        matrix
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.iter().any(Option::is_some))
+            .map(|(y, row)| {
+                let row = row
+                    .iter()
+                    .map(|block| match block {
+                        None => '.',
+                        Some(Block::Garbage) => 'X',
+                        Some(Block::Piece(kind, _)) => piece::kind_char(*kind),
+                    })
+                    .collect();
+                (y, row)
+            })
+            .collect()
+    }
+
+    /// Returns the cells of `tetromino`, sorted.
+    fn cells(tetromino: &Tetromino) -> Vec<(isize, isize)> {
+        let mut cells: Vec<_> = tetromino.position().iter().map(|p| (p.x, p.y)).collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn decodes_an_empty_field() {
+        let pages = decode("v115@vhAAgH").unwrap();
+        assert_eq!(pages.len(), 1);
+        let page = &pages[0];
+        assert!(rows(&page.matrix).is_empty());
+        assert!(page.current.is_none());
+        assert!(page.lock);
+        assert_eq!(page.comment, "");
+    }
+
+    #[test]
+    fn decodes_a_field_of_garbage() {
+        let pages = decode("v115@9gF8DeF8DeF8DeF8NeAgH").unwrap();
+        assert_eq!(pages.len(), 1);
+        let expected: Vec<_> = (16..20).map(|y| (y, "XXXXXX....".to_string())).collect();
+        assert_eq!(rows(&pages[0].matrix), expected);
+        assert!(pages[0].current.is_none());
+    }
+
+    #[test]
+    fn decodes_pages_with_comments_and_quizzes() {
+        let pages = decode(PAGES).unwrap();
+        assert_eq!(pages.len(), 3);
+
+        let first = &pages[0];
+        assert!(rows(&first.matrix).is_empty());
+        let current = first.current.as_ref().unwrap();
+        assert_eq!(current.kind(), Kind::T);
+        assert_eq!(current.direction(), Direction::Up);
+        assert_eq!(cells(current), [(3, 19), (4, 18), (4, 19), (5, 19)]);
+        assert!(first.lock);
+        assert_eq!(first.comment, "#Q=[S](T)IO");
+        assert_eq!(first.hold, Some(Kind::S));
+        assert_eq!(first.queue, [Kind::I, Kind::O]);
+
+        // the T is locked, and the comment kept
+        let second = &pages[1];
+        assert_eq!(rows(&second.matrix), [(18, "....T.....".to_string()), (19, "...TTT....".to_string())]);
+        assert_eq!(second.current.as_ref().map(Tetromino::kind), Some(Kind::T));
+        assert!(!second.lock);
+        assert_eq!(second.comment, "#Q=[S](T)IO");
+
+        let third = &pages[2];
+        assert_eq!(rows(&third.matrix), rows(&second.matrix));
+        assert!(third.current.is_none());
+        assert_eq!(third.comment, "done");
+        assert_eq!(third.hold, None);
+        assert!(third.queue.is_empty());
+    }
+
+    #[test]
+    fn decodes_the_url_of_a_viewer() {
+        let pages = decode("https://fumen.zui.jp/?v115@9gF8DeF8DeF8DeF8NeAgH").unwrap();
+        assert_eq!(rows(&pages[0].matrix).len(), 4);
+    }
+
+    #[test]
+    fn encodes_the_decoded_fields_back() {
+        for fumen in ["v115@vhAAgH", "v115@9gF8DeF8DeF8DeF8NeAgH"] {
+            assert_eq!(encode(&decode(fumen).unwrap()).unwrap(), fumen);
+        }
+    }
+
+    #[test]
+    fn decodes_the_encoded_pages_back() {
+        // the current piece of a quiz is written as the piece of its page, so only the pages
+        // are the same
+        let pages = decode(PAGES).unwrap();
+        let decoded = decode(&encode(&pages).unwrap()).unwrap();
+        assert_eq!(decoded.len(), pages.len());
+        for (page, decoded) in pages.iter().zip(&decoded) {
+            assert_eq!(rows(&decoded.matrix), rows(&page.matrix));
+            assert_eq!(decoded.current.as_ref().map(cells), page.current.as_ref().map(cells));
+            assert_eq!(decoded.lock, page.lock);
+            assert_eq!(decoded.comment, page.comment);
+            assert_eq!((decoded.hold, &decoded.queue), (page.hold, &page.queue));
+        }
+    }
+
+    #[test]
+    fn decodes_the_encoded_pages_of_a_game() {
+        use Action::*;
+        let mut game = Game::with_seed(Mode::Endless, 7);
+        for action in [MoveLeft, HardDrop, RotateClockwise, HardDrop, MoveRight, MoveRight, HardDrop, HardDrop] {
+            while game.cur_tetromino.is_none() {
+                game.step();
+            }
+            game.update(action);
+            game.release(action);
+            game.step();
+        }
+        let pages = game_pages(&game);
+        let decoded = decode(&encode(&pages).unwrap()).unwrap();
+        assert_eq!(decoded.len(), pages.len());
+        for (page, decoded) in pages.iter().zip(&decoded) {
+            assert_eq!(rows(&decoded.matrix), rows(&page.matrix));
+            assert_eq!(decoded.current.as_ref().map(cells), page.current.as_ref().map(cells));
+            assert_eq!(decoded.lock, page.lock);
+            assert_eq!(decoded.queue, page.queue);
+        }
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        for fumen in ["", "vhAAgH", "v114@vhAAgH", "https://fumen.zui.jp/?v110@vhAAgH"] {
+            assert_eq!(decode(fumen).err(), Some(Error::UnsupportedVersion), "{fumen:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_characters() {
+        assert_eq!(decode("v115@vh!AgH").err(), Some(Error::InvalidChar('!')));
+        assert_eq!(decode("v115@vhAAgé").err(), Some(Error::InvalidChar('é')));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        assert_eq!(decode("v115@vhAAg").err(), Some(Error::UnexpectedEnd));
+        assert_eq!(decode("v115@9gF8").err(), Some(Error::UnexpectedEnd));
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        // 190 empty cells then 240 more
+        assert_eq!(decode("v115@9gvhAAgH").err(), Some(Error::InvalidField));
+        // a block of value 9
+        assert_eq!(decode("v115@w/").err(), Some(Error::InvalidField));
+    }
+
+    #[test]
+    fn never_panics_on_a_cut_fumen() {
+        for end in 0..=PAGES.len() {
+            let _ = decode(&PAGES[..end]);
+        }
+    }
+}
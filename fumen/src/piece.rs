@@ -0,0 +1,158 @@
+use crate::{
+    Error,
+    field::{self, WIDTH},
+};
+use matrix::Matrix;
+use pieces::{Tetromino, TetrominoKind as Kind};
+use vecmath::{Direction, Point, Rotation};
+
+/// Row of the field fumen counts positions from (the highest one).
+const TOP: isize = 22;
+
+/// Returns the value fumen uses for each direction.
+pub(crate) fn encode_direction(direction: Direction) -> u32 {
+    match direction {
+        Direction::Down => 0,
+        Direction::Right => 1,
+        Direction::Up => 2,
+        Direction::Left => 3,
+    }
+}
+
+/// Returns the direction of a fumen's value.
+pub(crate) fn decode_direction(value: u32) -> Direction {
+    match value % 4 {
+        0 => Direction::Down,
+        1 => Direction::Right,
+        2 => Direction::Up,
+        _ => Direction::Left,
+    }
+}
+
+/// Returns the cells of `kind` facing `direction` around its SRS center (`y` bottom-up).
+fn blocks(kind: Kind, direction: Direction) -> [(isize, isize); 4] {
+    let up = match kind {
+        Kind::I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+        Kind::T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+        Kind::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        Kind::L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+        Kind::J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+        Kind::S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        Kind::Z => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+    };
+    up.map(|(x, y)| match direction {
+        Direction::Up => (x, y),
+        Direction::Right => (y, -x),
+        Direction::Down => (-x, -y),
+        Direction::Left => (-y, x),
+    })
+}
+
+/// Fumen doesn't store the SRS center of some pieces but a cell next to it.
+/// # Returns
+/// the offset from the stored cell to the SRS center.
+fn center_shift(kind: Kind, direction: Direction) -> (isize, isize) {
+    match (kind, direction) {
+        (Kind::O, Direction::Left) => (1, -1),
+        (Kind::O, Direction::Down) => (1, 0),
+        (Kind::O, Direction::Up) => (0, -1),
+        (Kind::I, Direction::Down) => (1, 0),
+        (Kind::I, Direction::Left) => (0, -1),
+        (Kind::S, Direction::Up) => (0, -1),
+        (Kind::S, Direction::Right) => (-1, 0),
+        (Kind::Z, Direction::Up) => (0, -1),
+        (Kind::Z, Direction::Left) => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+/// Returns the cells (`y` bottom-up) of a piece stored by fumen at `position`.
+pub(crate) fn decode_cells(kind: Kind, direction: Direction, position: u32) -> [(isize, isize); 4] {
+    let (dx, dy) = center_shift(kind, direction);
+    let x = (position as usize % WIDTH) as isize + dx;
+    let y = TOP - (position as usize / WIDTH) as isize + dy;
+    blocks(kind, direction).map(|(bx, by)| (x + bx, y + by))
+}
+
+/// Returns the position fumen stores for a piece with the given cells (`y` bottom-up).
+pub(crate) fn encode_position(kind: Kind, direction: Direction, cells: [(isize, isize); 4]) -> Result<u32, Error> {
+    let blocks = blocks(kind, direction);
+    let (dx, dy) = center_shift(kind, direction);
+    let x = min_x(&cells) - min_x(&blocks) - dx;
+    let y = min_y(&cells) - min_y(&blocks) - dy;
+    let position = (TOP - y) * WIDTH as isize + x;
+    if !(0..WIDTH as isize).contains(&x) || !(0..field::BLOCKS as isize).contains(&position) {
+        return Err(Error::OutOfField);
+    }
+    Ok(position as u32)
+}
+
+/// Returns the cells of `tetromino` in fumen's coordinates (`y` bottom-up).
+pub(crate) fn cells_of(tetromino: &Tetromino) -> [(isize, isize); 4] {
+    tetromino
+        .position()
+        .map(|p| (p.x, Matrix::HEIGHT as isize - 1 - p.y))
+}
+
+/// Builds the `Tetromino` which covers the given cells (`y` bottom-up).
+pub(crate) fn tetromino_at(kind: Kind, direction: Direction, cells: [(isize, isize); 4]) -> Tetromino {
+    let mut tetromino = Tetromino::new(kind);
+    for _ in 0..direction as usize {
+        tetromino.rotate(Rotation::Clockwise);
+    }
+    let target = cells.map(|(x, y)| (x, Matrix::HEIGHT as isize - 1 - y));
+    let current = tetromino.cells.map(|p| (p.x, p.y));
+    tetromino.offset = Point::new(
+        min_x(&target) - min_x(&current),
+        min_y(&target) - min_y(&current),
+    );
+    tetromino
+}
+
+#[inline]
+fn min_x(cells: &[(isize, isize); 4]) -> isize {
+    cells.iter().map(|(x, _)| *x).min().unwrap()
+}
+
+#[inline]
+fn min_y(cells: &[(isize, isize); 4]) -> isize {
+    cells.iter().map(|(_, y)| *y).min().unwrap()
+}
+
+/// Returns the letter of each kind of tetromino.
+pub(crate) fn kind_char(kind: Kind) -> char {
+    match kind {
+        Kind::I => 'I',
+        Kind::L => 'L',
+        Kind::O => 'O',
+        Kind::Z => 'Z',
+        Kind::T => 'T',
+        Kind::J => 'J',
+        Kind::S => 'S',
+    }
+}
+
+/// Returns the kind of tetromino of a letter, `None` if it isn't one.
+fn char_kind(c: char) -> Option<Kind> {
+    Some(match c {
+        'I' => Kind::I,
+        'L' => Kind::L,
+        'O' => Kind::O,
+        'Z' => Kind::Z,
+        'T' => Kind::T,
+        'J' => Kind::J,
+        'S' => Kind::S,
+        _ => return None,
+    })
+}
+
+/// Parses a quiz comment (`#Q=[hold](current)queue`).
+/// # Returns
+/// the hold, the current piece and the queue, `None` if `comment` isn't a quiz.
+pub(crate) fn parse_quiz(comment: &str) -> Option<(Option<Kind>, Option<Kind>, Vec<Kind>)> {
+    let rest = comment.strip_prefix("#Q=[")?;
+    let (hold, rest) = rest.split_once("](")?;
+    let (current, rest) = rest.split_once(')')?;
+    let queue = rest.chars().map_while(char_kind).collect();
+    Some((hold.chars().find_map(char_kind), current.chars().find_map(char_kind), queue))
+}
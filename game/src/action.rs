@@ -0,0 +1,38 @@
+/// An action a player can do on the `cur_tetromino`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+}
+
+impl Action {
+    /// Every action.
+    pub const ALL: [Self; 6] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::RotateClockwise,
+        Self::RotateCounterClockwise,
+    ];
+
+    /// Returns the letter of the action, as written in the replays and the online protocol.
+    pub fn letter(self) -> char {
+        match self {
+            Self::MoveLeft => 'L',
+            Self::MoveRight => 'R',
+            Self::SoftDrop => 'S',
+            Self::HardDrop => 'H',
+            Self::RotateClockwise => 'C',
+            Self::RotateCounterClockwise => 'A',
+        }
+    }
+    /// Returns the action of a letter, `None` if it isn't one.
+    pub fn from_letter(c: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.letter() == c)
+    }
+}
@@ -0,0 +1,25 @@
+/// Frames the game waits between two tetrominos, simulated per frame like the rest of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Delays {
+    /// Entry delay (ARE): frames between a placement and the next tetromino.
+    pub are: u32,
+    /// Frames the cleared rows stay in the matrix (while they are animated) before the rows
+    /// above them drop, followed by the `are`.
+    pub line_clear: u32,
+}
+
+impl Delays {
+    /// The next tetromino comes as soon as a tetromino is placed, rows are cleared at once.
+    pub const NONE: Self = Self { are: 0, line_clear: 0 };
+    /// Timings of the classic console game.
+    pub const CLASSIC: Self = Self { are: 10, line_clear: 20 };
+    /// Timings of the arcade games with a grade.
+    pub const TGM: Self = Self { are: 30, line_clear: 41 };
+}
+
+impl Default for Delays {
+    /// No delays: they are opt-in, with the `[delays]` of the config or a ruleset.
+    fn default() -> Self {
+        Self::NONE
+    }
+}
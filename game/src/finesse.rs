@@ -0,0 +1,178 @@
+use matrix::Matrix;
+use pieces::{Tetromino, TetrominoKind};
+use vecmath::{Direction, Point, Rotation};
+use std::collections::VecDeque;
+
+/// Rows a new tetromino is moved down by, so that it can rotate freely.
+const DEPTH: isize = 4;
+
+/// Returns the least number of moves and rotations needed to put a new tetromino
+/// of the same kind in the same columns and with the same shape as `tetromino`.
+///
+/// Holding a move key until the tetromino reaches the wall (the auto shift) is a single input,
+/// so that tapping it instead is a fault too.
+///
+/// Obstacles are ignored: the tetromino is thought as moved above the stack and then dropped.
+pub fn min_inputs(tetromino: &Tetromino) -> Option<u32> {
+    let target = shape(tetromino);
+    let matrix = Matrix::default();
+    let mut start = Tetromino::new(tetromino.kind());
+    start.offset += Point::new(0, DEPTH);
+
+    let mut visited = Vec::new();
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((cur, inputs)) = queue.pop_front() {
+        let state = (cur.direction() as usize, cur.offset.x);
+        if visited.contains(&state) {
+            continue;
+        }
+        visited.push(state);
+        if shape(&cur) == target {
+            return Some(inputs);
+        }
+        for input in 0..6 {
+            let mut next = cur.clone();
+            match input {
+                0 => next.move_lr(Direction::Left),
+                1 => next.move_lr(Direction::Right),
+                2 => next.rotate(Rotation::Clockwise),
+                3 => next.rotate(Rotation::CounterClockwise),
+                4 => shift_to_wall(&matrix, &mut next, Direction::Left),
+                _ => shift_to_wall(&matrix, &mut next, Direction::Right),
+            }
+            if !matrix.will_collide(&next) {
+                queue.push_back((next, inputs + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Returns every placement of a tetromino of `kind` on the floor of an empty matrix, each shape
+/// once (e.g. the two vertical I's in the same column are the same placement).
+pub fn placements(kind: TetrominoKind) -> Vec<Tetromino> {
+    let matrix = Matrix::default();
+    let mut placements: Vec<Tetromino> = Vec::new();
+    let mut facing = Tetromino::new(kind);
+    facing.offset += Point::new(0, DEPTH);
+    for _ in 0..4 {
+        let mut cur = facing.clone();
+        shift_to_wall(&matrix, &mut cur, Direction::Left);
+        loop {
+            let mut placed = cur.clone();
+            while !matrix.will_collide(&placed) {
+                placed.push_down();
+            }
+            placed.offset.y -= 1;
+            if !placements.iter().any(|other| same_placement(other, &placed)) {
+                placements.push(placed);
+            }
+            cur.move_lr(Direction::Right);
+            if matrix.will_collide(&cur) {
+                break;
+            }
+        }
+        facing.rotate(Rotation::Clockwise);
+    }
+    placements
+}
+
+/// Returns `true` if `a` and `b` take the same columns with the same shape.
+pub fn same_placement(a: &Tetromino, b: &Tetromino) -> bool {
+    shape(a) == shape(b)
+}
+
+/// Moves `tetromino` towards `direction` until it reaches the wall.
+fn shift_to_wall(matrix: &Matrix, tetromino: &mut Tetromino, direction: Direction) {
+    loop {
+        let mut next = tetromino.clone();
+        next.move_lr(direction);
+        if matrix.will_collide(&next) {
+            break;
+        }
+        *tetromino = next;
+    }
+}
+
+/// Returns the cells of `tetromino` sorted, with `y` relative to its highest cell.
+fn shape(tetromino: &Tetromino) -> [(isize, isize); 4] {
+    let position = tetromino.position();
+    let top = position.iter().map(|p| p.y).min().unwrap();
+    let mut cells = position.map(|p| (p.x, p.y - top));
+    cells.sort_unstable();
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a new tetromino of `kind` rotated by `rotations` and moved by `columns`
+    /// (negative to the left).
+    fn placed(kind: TetrominoKind, rotations: &[Rotation], columns: isize) -> Tetromino {
+        let mut tetromino = Tetromino::new(kind);
+        tetromino.offset += Point::new(0, DEPTH);
+        for &rotation in rotations {
+            tetromino.rotate(rotation);
+        }
+        tetromino.offset.x += columns;
+        tetromino
+    }
+
+    #[test]
+    fn placement_at_spawn_needs_no_input() {
+        for kind in [TetrominoKind::T, TetrominoKind::O, TetrominoKind::I] {
+            assert_eq!(min_inputs(&placed(kind, &[], 0)), Some(0), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn shift_to_the_wall_is_one_input() {
+        // the T spawns 3 columns from the left wall and 4 from the right one
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[], -3)), Some(1));
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[], 4)), Some(1));
+        assert_eq!(min_inputs(&placed(TetrominoKind::O, &[], -4)), Some(1));
+        // next to the wall: a tap
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[], -1)), Some(1));
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[], -2)), Some(2));
+        // shift to the wall and tap back
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[], 3)), Some(2));
+    }
+
+    #[test]
+    fn rotated_placements_count_the_rotations() {
+        use Rotation::*;
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[Clockwise], 0)), Some(1));
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[Clockwise, Clockwise], 0)), Some(2));
+        assert_eq!(min_inputs(&placed(TetrominoKind::T, &[CounterClockwise], -1)), Some(2));
+        // rotated then shifted to the left wall
+        let mut wall = placed(TetrominoKind::T, &[Clockwise], 0);
+        shift_to_wall(&Matrix::default(), &mut wall, Direction::Left);
+        assert_eq!(min_inputs(&wall), Some(2));
+    }
+
+    #[test]
+    fn each_shape_is_placed_once() {
+        let counts: Vec<_> = [
+            TetrominoKind::O,
+            TetrominoKind::I,
+            TetrominoKind::T,
+            TetrominoKind::S,
+            TetrominoKind::Z,
+            TetrominoKind::L,
+            TetrominoKind::J,
+        ]
+        .map(|kind| placements(kind).len())
+        .into();
+        assert_eq!(counts, [9, 17, 34, 17, 17, 34, 34]);
+    }
+
+    #[test]
+    fn placements_are_on_the_floor() {
+        for placement in placements(TetrominoKind::L) {
+            let bottom = placement.position().iter().map(|point| point.y).max().unwrap();
+            assert_eq!(bottom, Matrix::HEIGHT as isize - 1);
+            assert!(min_inputs(&placement).is_some());
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Game;
+use std::time::Duration;
+use vecmath::Direction;
+
+/// How the keys held by a player repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handling {
+    /// Delayed auto shift: time a move key is held before the tetromino shifts on its own.
+    pub das: Duration,
+    /// Auto repeat rate: time between two shifts once `das` is reached (zero shifts to the wall).
+    pub arr: Duration,
+    /// Soft drop factor: how many times the soft drop is faster than gravity
+    /// (zero drops to the floor).
+    pub sdf: u32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: Duration::from_millis(167),
+            arr: Duration::from_millis(33),
+            sdf: 20,
+        }
+    }
+}
+
+/// The state of the move keys, for the auto shift.
+#[derive(Clone, Default)]
+pub(crate) struct Shift {
+    left: bool,
+    right: bool,
+    /// Direction of the last move key pressed which is still held.
+    direction: Option<Direction>,
+    /// Time the `direction` is held.
+    held: Duration,
+    /// Shifts done since `das` was reached.
+    repeats: u32,
+}
+
+impl Shift {
+    /// Handles a press of the key moving towards `direction`.
+    pub(crate) fn press(&mut self, direction: Direction) {
+        self.set(direction, true);
+        self.charge(Some(direction));
+    }
+    /// Handles a release of the key moving towards `direction`: the other one takes over
+    /// (charging again) if it is still held.
+    pub(crate) fn release(&mut self, direction: Direction) {
+        self.set(direction, false);
+        if self.direction == Some(direction) {
+            let other = match direction {
+                Direction::Left if self.right => Some(Direction::Right),
+                Direction::Right if self.left => Some(Direction::Left),
+                _ => None,
+            };
+            self.charge(other);
+        }
+    }
+    /// Advances the time held by a frame.
+    /// # Returns
+    /// the direction to shift towards and how many times (`u32::MAX` to the wall).
+    pub(crate) fn step(&mut self, handling: &Handling) -> Option<(Direction, u32)> {
+        let direction = self.direction?;
+        self.held += Game::FRAME;
+        if self.held < handling.das {
+            return None;
+        }
+        if handling.arr.is_zero() {
+            return Some((direction, u32::MAX));
+        }
+        let repeats = 1 + ((self.held - handling.das).as_nanos() / handling.arr.as_nanos()) as u32;
+        let shifts = repeats - self.repeats;
+        self.repeats = repeats;
+        Some((direction, shifts))
+    }
+    fn set(&mut self, direction: Direction, held: bool) {
+        match direction {
+            Direction::Left => self.left = held,
+            Direction::Right => self.right = held,
+            _ => {}
+        }
+    }
+    fn charge(&mut self, direction: Option<Direction>) {
+        self.direction = direction;
+        self.held = Duration::ZERO;
+        self.repeats = 0;
+    }
+}
@@ -1,18 +1,66 @@
 //! # Game
 //! Contains the struct to play the game
 
-use matrix::Matrix;
-use pieces::Tetromino;
+/// Module of the actions a player can do
+pub mod action;
+/// Module of the entry and line clear delays
+pub mod delays;
+/// Module to measure the finesse of the placements
+pub mod finesse;
+/// Module of the auto shift and soft drop settings
+pub mod handling;
+/// Module of the modes and of how a game can end
+pub mod mode;
+/// Module to record and play again the inputs of a game
+pub mod replay;
+/// Module of the points given by guideline scoring
+pub mod scoring;
+/// Module of the statistics of a game
+pub mod stats;
+/// Module to play games against each other
+pub mod versus;
+
+pub use action::Action;
+pub use delays::Delays;
+pub use handling::Handling;
+pub use mode::{Mode, Outcome};
+pub use replay::Replay;
+pub use stats::Stats;
+
+use matrix::{Block, Matrix};
+use pieces::{Tetromino, TetrominoKind};
 use vecmath::{
     Direction,
+    Point,
     Rotation
 };
-use std::{
-    time::Duration,
-    process
-};
-use rand::random_range;
-use sdl2::keyboard::Keycode;
+use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Offset basis and prime of the 64-bit FNV-1a hash.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Returns the 64-bit FNV-1a hash of `bytes`, the same on every platform (unlike the hashers
+/// of `std`, which may change between Rust versions).
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Random number generator of a game (bag and garbage holes), seeded so that two games with the
+/// same seed and inputs play out the same.
+///
+/// ChaCha8 gives the same numbers on every platform and version of `rand_chacha`, unlike
+/// `StdRng`: peers, replays and golden images rely on it.
+#[derive(Clone)]
+struct GameRng(ChaCha8Rng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(ChaCha8Rng::seed_from_u64(0))
+    }
+}
 
 /// Game struct. Handles the game logic and the score.
 #[derive(Clone, Default)]
@@ -21,19 +69,87 @@ pub struct Game {
     pub cur_tetromino: Option<Tetromino>,
     pub bag: Vec<Tetromino>,
     pub score: u32,
+    pub level: u32,
+    /// Consecutive placements which cleared rows after the first one, `None` if the last didn't.
+    pub combo: Option<u32>,
+    /// `true` if the last clear was difficult (see `scoring::is_difficult`).
+    pub back_to_back: bool,
+    /// Time the `cur_tetromino` takes to drop by a row (see `Mode::gravity`).
     pub tick: Duration,
+    /// `true` while the soft drop key is held.
+    pub soft_dropping: bool,
+    /// Time since the `cur_tetromino` last dropped by gravity.
+    pub since_drop: Duration,
+    /// Each placed tetromino with the matrix it was placed on, first to last.
+    pub history: Vec<(Matrix, Tetromino)>,
+    pub mode: Mode,
+    /// `false` until the first input: the timer and gravity don't run before it.
+    pub started: bool,
+    /// `Some` once the game is over.
+    pub outcome: Option<Outcome>,
+    pub stats: Stats,
+    /// Garbage rows received and not placed yet, one batch per attack.
+    pub incoming: Vec<u32>,
+    /// Garbage rows sent and not delivered to the opponent yet.
+    pub outgoing: u32,
+    /// Seed of the random pieces and garbage holes.
+    pub seed: u64,
+    /// How the keys held by the player repeat.
+    pub handling: Handling,
+    /// Frames waited after the placements.
+    pub delays: Delays,
+    /// Rows full after the last placement, top to bottom, which are cleared when the `delay`
+    /// ends (empty if there is no line clear delay running).
+    pub clearing: Vec<usize>,
+    /// Frames left before the `clearing` rows are cleared, or before the next tetromino comes
+    /// if there are none.
+    pub delay: u32,
+    /// Placement the `cur_tetromino` should reach in the finesse trainer.
+    pub target: Option<Tetromino>,
+    /// Frame of the last fault or missed target in the finesse trainer.
+    pub last_fault: Option<u32>,
+    rng: GameRng,
+    /// The move keys held, for the auto shift.
+    shift: handling::Shift,
+    /// Moves and rotations used on the `cur_tetromino`.
+    inputs: u32,
+    /// `true` if the `cur_tetromino` was soft dropped (finesse isn't measured then).
+    soft_dropped: bool,
+    /// `true` if the last successful action on the `cur_tetromino` was a rotation.
+    last_rotated: bool,
 }
 
 impl Game {
     pub const BASE_TICK: Duration = Duration::from_millis(600);
-    const FAST_TICK: Duration = Duration::from_millis(Self::BASE_TICK.as_millis() as u64 / 20);
+    /// Shortest time a grounded tetromino waits before being placed while soft dropping.
+    const SOFT_LOCK_DELAY: Duration = Duration::from_millis(30);
+    /// Time a grounded tetromino waits before being placed when gravity is faster than it.
+    const LOCK_DELAY: Duration = Duration::from_millis(500);
+    /// Frames simulated per second.
+    pub const FPS: u64 = 60;
+    /// Duration of a frame.
+    pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / Self::FPS);
+    
+    pub fn new(mode: Mode) -> Self {
+        Self::with_seed(mode, rand::random())
+    }
     
-    pub fn new() -> Self {
+    /// Creates a game whose pieces and garbage holes are drawn from `seed`.
+    pub fn with_seed(mode: Mode, seed: u64) -> Self {
         let mut game = Self {
+            seed,
+            rng: GameRng(ChaCha8Rng::seed_from_u64(seed)),
             bag: Vec::with_capacity(Tetromino::SIZE),
-            tick: Self::BASE_TICK,
+            tick: mode.gravity(mode.level(0)),
+            level: mode.level(0),
+            mode,
             ..Default::default()
         };
+        if let Mode::Dig { rows, .. } = mode {
+            for _ in 0..rows.min(Mode::MAX_DIG_ROWS) {
+                game.add_garbage();
+            }
+        }
         game.refill_bag();
         game.put_tetromino();
         game
@@ -45,68 +161,265 @@ impl Game {
     #[inline]
     pub fn refill_bag(&mut self) {
         assert!(self.bag.is_empty());
-        self.bag.extend_from_slice(&Tetromino::bag());
+        self.bag.extend_from_slice(&Tetromino::bag(&mut self.rng.0));
     }
     
-    /// Replace the `cur_tetromino` to the last tetromino in the bag if it is `None`
-    #[inline(always)]
+    /// Replace the `cur_tetromino` to the last tetromino in the bag if it is `None`.
+    ///
+    /// A new bag is put before the next tetrominos when fewer than 7 are left,
+    /// so that a whole bag can always be previewed.
+    ///
+    /// The game is topped out if the new tetromino overlaps the matrix.
     fn put_tetromino(&mut self) {
         self.cur_tetromino = self.bag.pop();
+        if self.bag.len() < Tetromino::SIZE {
+            let bag = Tetromino::bag(&mut self.rng.0);
+            self.bag.splice(0..0, bag);
+        }
+        self.inputs = 0;
+        self.soft_dropped = false;
+        self.last_rotated = false;
+        if self.mode == Mode::Finesse && self.target.is_none() && let Some(cur) = &self.cur_tetromino {
+            let mut placements = finesse::placements(cur.kind());
+            let i = self.rng.0.random_range(0..placements.len());
+            self.target = Some(placements.swap_remove(i));
+        }
+        if self.cur_tetromino.as_ref().is_some_and(|new| self.matrix.will_collide(new)) {
+            self.outcome = Some(Outcome::ToppedOut);
+        }
     }
     
-    /// Moves/Rotates the `cur_tetromino` left, right, clockwise or counterclockwise.
-    pub fn update(&mut self, key: Keycode) {
-        match key {
-            Keycode::Left | Keycode::A => {                // Move left
+    /// Starts the timer and gravity if they aren't running.
+    #[inline]
+    pub fn start(&mut self) {
+        self.started = true;
+    }
+    
+    /// Simulates a frame: runs the timer and drops the `cur_tetromino` by gravity.
+    pub fn step(&mut self) {
+        if !self.started || self.outcome.is_some() {
+            return;
+        }
+        self.stats.frames += 1;
+        if self.mode.time_limit().is_some_and(|limit| self.stats.time() >= limit) {
+            self.outcome = Some(Outcome::Completed);
+            return;
+        }
+        let rise_frames = (Mode::RISE_INTERVAL.as_millis() as u64 * Self::FPS / 1000) as u32;
+        if let Mode::Dig { rising: true, .. } = self.mode && self.stats.frames.is_multiple_of(rise_frames) {
+            self.add_garbage();
+            if self.outcome.is_some() {
+                return;
+            }
+        }
+        if let Some((direction, shifts)) = self.shift.step(&self.handling) {
+            for _ in 0..shifts {
+                if !self.try_move(direction) {
+                    break;
+                }
+            }
+        }
+        if self.cur_tetromino.is_none() {
+            // the auto shift charges during the delays
+            self.wait();
+            return;
+        }
+        self.since_drop += Self::FRAME;
+        let tick = match (self.soft_dropping, self.handling.sdf) {
+            (false, _) => self.tick,
+            // drops to the floor
+            (true, 0) => Duration::ZERO,
+            (true, sdf) => self.tick / sdf,
+        };
+        if self.is_grounded() {
+            let lock_delay = if self.tick < Self::LOCK_DELAY { Self::LOCK_DELAY } else { tick.max(Self::SOFT_LOCK_DELAY) };
+            if self.since_drop >= lock_delay {
+                self.since_drop = Duration::ZERO;
+                self.place();
+            }
+            return;
+        }
+        // gravity can be faster than a frame
+        while self.since_drop >= tick && self.try_drop() {
+            self.since_drop -= tick;
+            if self.soft_dropping {
+                self.score += scoring::SOFT_DROP;
+            }
+        }
+    }
+    
+    /// Moves/Rotates/Drops the `cur_tetromino` based on `action`.
+    pub fn update(&mut self, action: Action) {
+        if self.outcome.is_some() {
+            return;
+        }
+        self.start();
+        self.stats.keys += 1;
+        match action {
+            Action::MoveLeft => {
+                self.inputs += 1;
+                self.shift.press(Direction::Left);
                 self.try_move(Direction::Left);
             }
-            Keycode::Right | Keycode::D => {               // Move right
+            Action::MoveRight => {
+                self.inputs += 1;
+                self.shift.press(Direction::Right);
                 self.try_move(Direction::Right);
             }
-            Keycode::Down | Keycode::S => {                // Soft drop
-                self.tick = Self::FAST_TICK;
+            Action::SoftDrop => {
+                self.soft_dropped = true;
+                self.soft_dropping = true;
             }
-            Keycode::Space => {                            // Hard drop
-                self.hard_drop();
-            }
-            Keycode::Up | Keycode::W | Keycode::RCTRL => { // Rotate clockwise
+            Action::HardDrop => self.hard_drop(),
+            Action::RotateClockwise => {
+                self.inputs += 1;
                 self.try_rotate(Rotation::Clockwise);
             }
-            Keycode::Q | Keycode::LCTRL => {               // Rotate counterclockwise
+            Action::RotateCounterClockwise => {
+                self.inputs += 1;
                 self.try_rotate(Rotation::CounterClockwise);
             }
+        }
+    }
+    
+    /// Handles the release of the key bound to `action` (ends the soft drop or the auto shift).
+    pub fn release(&mut self, action: Action) {
+        match action {
+            Action::SoftDrop => self.soft_dropping = false,
+            Action::MoveLeft => self.shift.release(Direction::Left),
+            Action::MoveRight => self.shift.release(Direction::Right),
             _ => {}
         }
     }
     
-    fn check_rows(&mut self) {
-        for (i, row) in self.matrix.iter().enumerate().rev() {
-            if row.iter().all(Option::is_some) {
-                self.score += 100;
-                self.matrix.push_down(i);
-                break;
+    /// Adds a batch of `rows` garbage rows to the `incoming` ones.
+    pub fn receive(&mut self, rows: u32) {
+        if rows > 0 {
+            self.incoming.push(rows);
+        }
+    }
+    
+    /// Takes the garbage rows sent since the last call.
+    pub fn take_outgoing(&mut self) -> u32 {
+        std::mem::take(&mut self.outgoing)
+    }
+    
+    /// Returns a hash of the state of the game, equal on two games which played out the same
+    /// (on any platform and build, to compare the games of two peers).
+    pub fn checksum(&self) -> u64 {
+        let mut bytes = Vec::new();
+        for block in self.matrix.iter().flatten() {
+            bytes.extend(match block {
+                None => [0, 0, 0],
+                Some(Block::Garbage) => [1, 0, 0],
+                Some(Block::Piece(kind, links)) => [2, kind.index() as u8, links.bits()],
+            });
+        }
+        bytes.push(self.cur_tetromino.is_some() as u8);
+        if let Some(cur) = &self.cur_tetromino {
+            bytes.push(cur.kind().index() as u8);
+            for coord in cur.position() {
+                bytes.extend((coord.x as i64).to_le_bytes());
+                bytes.extend((coord.y as i64).to_le_bytes());
+            }
+        }
+        // the lengths of the lists first, not to mistake the end of one for the start of the next
+        for len in [self.bag.len(), self.clearing.len(), self.incoming.len()] {
+            bytes.extend((len as u32).to_le_bytes());
+        }
+        bytes.extend(self.bag.iter().map(|tetromino| tetromino.kind().index() as u8));
+        for value in [self.score, self.stats.frames, self.stats.lines, self.outgoing, self.delay] {
+            bytes.extend(value.to_le_bytes());
+        }
+        for row in &self.clearing {
+            bytes.extend((*row as u64).to_le_bytes());
+        }
+        for lines in &self.incoming {
+            bytes.extend(lines.to_le_bytes());
+        }
+        bytes.push(match self.outcome {
+            None => 0,
+            Some(Outcome::Completed) => 1,
+            Some(Outcome::ToppedOut) => 2,
+        });
+        fnv1a(&bytes)
+    }
+    
+    /// Returns the points scored per minute since the timer started.
+    pub fn points_per_minute(&self) -> f64 {
+        match self.stats.time().as_secs_f64() {
+            0.0 => 0.0,
+            secs => self.score as f64 * 60.0 / secs,
+        }
+    }
+    
+    /// Advances the `delay` by a frame: once it ends, the `clearing` rows are cleared
+    /// (then the entry delay runs), or the next tetromino comes.
+    fn wait(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        if self.delay > 0 {
+            return;
+        }
+        if !self.clearing.is_empty() {
+            self.clear_rows();
+            self.delay = self.delays.are;
+            if self.delay > 0 {
+                return;
             }
         }
+        self.put_tetromino();
+    }
+    
+    /// Removes the `clearing` rows, dropping the rows above them.
+    fn clear_rows(&mut self) {
+        // top to bottom, so that the rows left to clear don't move
+        for i in std::mem::take(&mut self.clearing) {
+            self.matrix.push_down(i);
+        }
     }
     
-    /// Tries to rotate `cur_tetromino` or does nothing if it can't.
+    /// Returns the full rows of the matrix, top to bottom.
+    fn full_rows(&self) -> Vec<usize> {
+        self.matrix
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.iter().all(Option::is_some))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    
+    /// Tries to rotate `cur_tetromino` or does nothing if it can't (or is `None`).
     fn try_rotate(&mut self, rotation: Rotation) {
-        let mut new = self.cur_tetromino.clone().unwrap();
+        let Some(mut new) = self.cur_tetromino.clone() else { return };
         new.rotate(rotation);
         if self.matrix.will_collide(&new) {
             return;
         }
         self.cur_tetromino = Some(new);
+        self.last_rotated = true;
     }
     
-    /// Tries to move `cur_tetromino` left/right or does nothing if it can't.
-    fn try_move(&mut self, direction: Direction) {
-        let mut new = self.cur_tetromino.clone().unwrap();
+    /// Tries to move `cur_tetromino` left/right or does nothing if it can't (or is `None`).
+    /// # Returns
+    /// `true` if it moved
+    fn try_move(&mut self, direction: Direction) -> bool {
+        let Some(mut new) = self.cur_tetromino.clone() else { return false };
         new.move_lr(direction);
         if self.matrix.will_collide(&new) {
-            return;
+            return false;
         }
         self.cur_tetromino = Some(new);
+        self.last_rotated = false;
+        true
+    }
+    
+    /// Returns `true` if the `cur_tetromino` can't drop any further.
+    fn is_grounded(&self) -> bool {
+        self.cur_tetromino.as_ref().is_some_and(|cur| {
+            let mut new = cur.clone();
+            new.push_down();
+            self.matrix.will_collide(&new)
+        })
     }
     
     /// Drop the `cur_tetromino`'s y down by 1 if possible.
@@ -119,37 +432,218 @@ impl Game {
             return false;
         }
         self.cur_tetromino = Some(new);
+        self.last_rotated = false;
         true
     }
     
+    /// Returns `true` if `tetromino` is a T which was just rotated with at least 3 of the 4 cells
+    /// diagonal to its center occupied (walls and floor included).
+    fn is_t_spin(&self, tetromino: &Tetromino) -> bool {
+        if tetromino.kind() != TetrominoKind::T || !self.last_rotated {
+            return false;
+        }
+        [(0, 0), (2, 0), (0, 2), (2, 2)]
+            .into_iter()
+            .filter(|corner| self.matrix.is_occupied(tetromino.offset + Point::from(*corner)))
+            .count() >= 3
+    }
+    
     /// Moves the `cur_tetromino` into the matrix. Must be used if the tetromino touches the ground
     /// or a tetromino
     /// 
+    /// The full rows are cleared after the line clear delay, and the next tetromino comes
+    /// after the entry delay (see `Delays`): the `cur_tetromino` is `None` until then.
+    ///
+    /// The game is topped out if the tetromino is (even partially) above the matrix,
+    /// and completed if the goal of the `mode` is reached (see `is_completed`).
     /// # Panics
-    /// if one or more of its cells are already occupied
+    /// if `cur_tetromino` is `None`
     pub fn place(&mut self) {
         let Some(tetromino) = self.cur_tetromino.take() else {
             panic!("cur_tetromino is None");
         };
         if tetromino.position().iter().any(|point| point.y < 0 || point.x < 0) {
-            println!("You lost");
-            process::exit(0);
+            self.outcome = Some(Outcome::ToppedOut);
+            return;
         }
-        self.matrix.place_piece(tetromino);
-        self.score += random_range(3..=8);
-        while self.matrix.iter().any(|row| row.iter().all(Option::is_some)) {
-            self.check_rows();
+        self.stats.pieces += 1;
+        self.stats.distribution[tetromino.kind().index()] += 1;
+        let fault = !self.soft_dropped && finesse::min_inputs(&tetromino).is_some_and(|min| self.inputs > min);
+        if fault {
+            self.stats.finesse_faults += 1;
+        }
+        if self.mode == Mode::Finesse {
+            self.train(&tetromino, fault);
+            return;
+        }
+        let t_spin = self.is_t_spin(&tetromino);
+        if t_spin {
+            self.stats.t_spins += 1;
+        }
+        self.history.push((self.matrix.clone(), tetromino.clone()));
+        if !self.matrix.place_piece(tetromino) {
+            self.outcome = Some(Outcome::ToppedOut);
+            return;
+        }
+        self.clearing = self.full_rows();
+        let lines = self.clearing.len() as u32;
+        self.stats.lines += lines;
+        let attack = self.score_clear(lines, t_spin);
+        self.stats.attack += attack;
+        if lines > 0 {
+            self.cancel_garbage(attack);
+        } else {
+            self.place_garbage();
+            if self.outcome.is_some() {
+                return;
+            }
+        }
+        self.level = self.mode.level(self.stats.lines);
+        self.tick = self.mode.gravity(self.level);
+        while (self.stats.splits.len() as u32) < self.stats.lines / Mode::SPLIT_LINES {
+            let time = self.stats.time();
+            self.stats.splits.push(time);
+        }
+        if lines == 0 || self.delays.line_clear == 0 {
+            self.clear_rows();
+        }
+        if self.is_completed() {
+            self.clear_rows();
+            self.outcome = Some(Outcome::Completed);
+            return;
+        }
+        self.delay = if self.clearing.is_empty() { self.delays.are } else { self.delays.line_clear };
+        if self.delay == 0 {
+            self.put_tetromino();
+        }
+    }
+    /// Checks a placement of the finesse trainer, which leaves the matrix empty: the same
+    /// tetromino comes again with the same `target` after a `fault` or if it missed it,
+    /// else the next one comes with a new target.
+    fn train(&mut self, tetromino: &Tetromino, fault: bool) {
+        let missed = self.target.as_ref().is_some_and(|target| !finesse::same_placement(target, tetromino));
+        if fault || missed {
+            self.stats.retries += 1;
+            self.last_fault = Some(self.stats.frames);
+            self.bag.push(Tetromino::new(tetromino.kind()));
+        } else {
+            self.target = None;
+        }
+        if self.is_completed() {
+            self.outcome = Some(Outcome::Completed);
+            return;
+        }
+        self.delay = self.delays.are;
+        if self.delay == 0 {
+            self.put_tetromino();
+        }
+    }
+    /// Returns `true` if the goal of the `mode` is reached: enough lines are cleared,
+    /// no garbage is left in a dig, or enough tetrominos are placed without a fault in the
+    /// finesse trainer.
+    fn is_completed(&self) -> bool {
+        match self.mode {
+            Mode::Finesse => self.stats.pieces - self.stats.retries >= Mode::FINESSE_PIECES,
+            Mode::Dig { .. } => {
+                let clearing = self.clearing.iter().filter(|&&i| self.matrix[i].contains(&Some(Block::Garbage))).count();
+                self.matrix.garbage_rows() == clearing
+            }
+            mode => mode.goal().is_some_and(|goal| self.stats.lines >= goal),
+        }
+    }
+    /// Pushes a garbage row into the matrix from the bottom, with its hole in a different column
+    /// than the one below it.
+    ///
+    /// The `cur_tetromino` is moved up if it overlaps the new row, and the game is topped out
+    /// if blocks are pushed out of the matrix.
+    pub fn add_garbage(&mut self) {
+        let hole = self.garbage_hole();
+        self.push_garbage(hole);
+    }
+    /// Returns a random column for the hole of a new garbage row, different from the one of
+    /// the garbage row at the bottom (if any).
+    fn garbage_hole(&mut self) -> usize {
+        let bottom = &self.matrix[Matrix::HEIGHT - 1];
+        let last_hole = bottom
+            .iter()
+            .position(Option::is_none)
+            .filter(|_| bottom.contains(&Some(Block::Garbage)));
+        loop {
+            let hole = self.rng.0.random_range(0..Matrix::WIDTH);
+            if Some(hole) != last_hole {
+                break hole;
+            }
+        }
+    }
+    /// Pushes a garbage row with its hole at `hole` into the matrix from the bottom.
+    fn push_garbage(&mut self, hole: usize) {
+        if !self.matrix.push_up(Matrix::garbage_row(hole)) {
+            self.outcome = Some(Outcome::ToppedOut);
+        }
+        for row in &mut self.clearing {
+            *row = row.saturating_sub(1);
+        }
+        if let Some(cur) = &mut self.cur_tetromino && self.matrix.will_collide(cur) {
+            cur.offset.y -= 1;
         }
-        self.put_tetromino();
+    }
+    /// Places every `incoming` garbage batch, each with its hole in a single column.
+    fn place_garbage(&mut self) {
+        for rows in std::mem::take(&mut self.incoming) {
+            let hole = self.garbage_hole();
+            for _ in 0..rows {
+                self.push_garbage(hole);
+            }
+        }
+    }
+    /// Cancels the `incoming` garbage with `attack` rows, oldest first,
+    /// and sends what is left to the opponent.
+    fn cancel_garbage(&mut self, mut attack: u32) {
+        while attack > 0 && let Some(rows) = self.incoming.first_mut() {
+            let cancelled = attack.min(*rows);
+            *rows -= cancelled;
+            attack -= cancelled;
+            if *rows == 0 {
+                self.incoming.remove(0);
+            }
+        }
+        self.outgoing += attack;
+    }
+    /// Adds the points of a placement which cleared `lines` rows.
+    /// # Returns
+    /// the garbage rows it attacks with.
+    fn score_clear(&mut self, lines: u32, t_spin: bool) -> u32 {
+        let mut points = scoring::clear_points(lines, t_spin);
+        let mut attack = 0;
+        if lines > 0 {
+            let difficult = scoring::is_difficult(lines, t_spin);
+            let back_to_back = difficult && self.back_to_back;
+            if back_to_back {
+                points += points / 2;
+            }
+            self.back_to_back = difficult;
+            self.combo = Some(self.combo.map_or(0, |combo| combo + 1));
+            let combo = self.combo.unwrap();
+            points += scoring::COMBO * combo;
+            // the full rows aren't cleared yet
+            let perfect_clear = self.matrix.iter().all(|row| row.iter().all(Option::is_some) || row.iter().all(Option::is_none));
+            attack = scoring::attack(lines, t_spin, back_to_back, combo, perfect_clear);
+        } else {
+            self.combo = None;
+        }
+        self.score += points * self.level;
+        attack
     }
     /// Instantly drops the `cur_tetromino` as down as possible and `place` it.
     fn hard_drop(&mut self) {
-        if self.cur_tetromino.as_ref().unwrap().position().iter().any(|point| point.y < 0 || point.x < 0) {
-            return;
+        let Some(ghost) = self.get_ghost() else { return };
+        let distance = (ghost.offset.y - self.cur_tetromino.as_ref().unwrap().offset.y) as u32;
+        if distance > 0 {
+            self.score += scoring::HARD_DROP * distance;
+            self.last_rotated = false;
         }
-        self.cur_tetromino = self.get_ghost();
+        self.cur_tetromino = Some(ghost);
         self.place();
-        self.score += 30;
     }
     /// Returns the shadow of the `cur_tetromino` placed as down as possible in the `matrix`.
     pub fn get_ghost(&self) -> Option<Tetromino> {
@@ -161,3 +655,254 @@ impl Game {
         Some(ghost)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Does each of `actions` on its own frame, waiting for the next piece after a placement.
+    fn play(game: &mut Game, actions: &[Action]) {
+        for &action in actions {
+            while game.cur_tetromino.is_none() && game.outcome.is_none() {
+                game.step();
+            }
+            game.update(action);
+            game.release(action);
+            game.step();
+        }
+    }
+
+    /// Returns a started game of `mode` with the `delays` whose `cur_tetromino`, an I, clears the
+    /// bottom row (of garbage) when it is hard dropped.
+    fn ready_to_clear(mode: Mode, delays: Delays) -> Game {
+        ready_to_drop(mode, delays, Tetromino::new(TetrominoKind::I))
+    }
+
+    /// Returns a started game of `mode` with the `delays` whose `cur_tetromino` is `tetromino`,
+    /// which clears every row it lands on (of garbage) when it is hard dropped.
+    fn ready_to_drop(mode: Mode, delays: Delays, tetromino: Tetromino) -> Game {
+        let mut game = Game::with_seed(mode, 42);
+        game.matrix = Matrix::default();
+        game.delays = delays;
+        game.cur_tetromino = Some(tetromino);
+        game.start();
+        let cells = game.get_ghost().unwrap().position();
+        for point in cells {
+            let row = &mut game.matrix[point.y as usize];
+            for (x, cell) in row.iter_mut().enumerate() {
+                if !cells.contains(&Point::new(x as isize, point.y)) {
+                    *cell = Some(Block::Garbage);
+                }
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn no_input_is_taken_during_the_entry_delay() {
+        let mut game = Game::with_seed(Mode::Endless, 42);
+        game.delays = Delays { are: 10, line_clear: 0 };
+        game.update(Action::HardDrop);
+        for _ in 0..9 {
+            assert!(game.cur_tetromino.is_none());
+            for action in [Action::MoveLeft, Action::RotateClockwise, Action::HardDrop] {
+                game.update(action);
+                game.release(action);
+            }
+            game.step();
+        }
+        assert!(game.cur_tetromino.is_none());
+        assert_eq!(game.stats.pieces, 1);
+        game.step();
+        let next = game.cur_tetromino.clone().expect("the next tetromino comes after the entry delay");
+        // the inputs of the delay didn't move it
+        assert_eq!(next.offset, Tetromino::new(next.kind()).offset);
+        assert_eq!(next.direction(), Tetromino::new(next.kind()).direction());
+    }
+
+    #[test]
+    fn rows_are_removed_after_the_line_clear_delay() {
+        let mut game = ready_to_clear(Mode::Endless, Delays { are: 0, line_clear: 20 });
+        game.update(Action::HardDrop);
+        assert_eq!(game.stats.lines, 1);
+        assert_eq!(game.clearing, [Matrix::HEIGHT - 1]);
+        for _ in 0..19 {
+            game.step();
+            assert!(game.matrix[Matrix::HEIGHT - 1].iter().all(Option::is_some));
+            assert!(game.cur_tetromino.is_none());
+        }
+        game.step();
+        assert!(game.clearing.is_empty());
+        assert!(game.matrix.iter().flatten().all(Option::is_none));
+        assert!(game.cur_tetromino.is_some());
+    }
+
+    #[test]
+    fn no_delays_clear_at_once() {
+        assert_eq!(Delays::default(), Delays::NONE);
+        let mut game = ready_to_clear(Mode::Endless, Delays::NONE);
+        game.update(Action::HardDrop);
+        assert_eq!(game.stats.lines, 1);
+        assert!(game.clearing.is_empty());
+        assert!(game.matrix.iter().flatten().all(Option::is_none));
+        assert!(game.cur_tetromino.is_some());
+    }
+
+    #[test]
+    fn garbage_holes_differ_from_the_row_below() {
+        for seed in 0..20 {
+            let game = Game::with_seed(Mode::Dig { rows: Mode::MAX_DIG_ROWS, rising: false }, seed);
+            let holes: Vec<_> = game.matrix
+                .iter()
+                .filter(|row| row.contains(&Some(Block::Garbage)))
+                .map(|row| row.iter().position(Option::is_none).unwrap())
+                .collect();
+            assert_eq!(holes.len(), Mode::MAX_DIG_ROWS as usize);
+            assert!(holes.windows(2).all(|pair| pair[0] != pair[1]), "seed {seed}: {holes:?}");
+        }
+    }
+
+    #[test]
+    fn dig_is_completed_when_the_last_garbage_row_is_cleared() {
+        let mut game = ready_to_clear(Mode::Dig { rows: 1, rising: false }, Delays::NONE);
+        assert_eq!(game.matrix.garbage_rows(), 1);
+        game.update(Action::HardDrop);
+        assert_eq!(game.outcome, Some(Outcome::Completed));
+    }
+
+    #[test]
+    fn dig_is_completed_before_the_line_clear_delay() {
+        let mut game = ready_to_clear(Mode::Dig { rows: 1, rising: false }, Delays { are: 0, line_clear: 20 });
+        game.update(Action::HardDrop);
+        assert_eq!(game.outcome, Some(Outcome::Completed));
+        assert_eq!(game.matrix.garbage_rows(), 0);
+    }
+
+    #[test]
+    fn dig_goes_on_while_garbage_is_left() {
+        let mut game = ready_to_clear(Mode::Dig { rows: 2, rising: false }, Delays::NONE);
+        // the same garbage above the row to clear, which the I falls through
+        game.matrix[Matrix::HEIGHT - 2] = game.matrix[Matrix::HEIGHT - 1];
+        game.update(Action::HardDrop);
+        assert_eq!(game.stats.lines, 1);
+        assert_eq!(game.outcome, None);
+        assert_eq!(game.matrix.garbage_rows(), 1);
+    }
+
+    #[test]
+    fn attack_cancels_the_incoming_garbage_oldest_first() {
+        let mut game = Game::with_seed(Mode::Versus, 42);
+        game.receive(2);
+        game.receive(3);
+        game.cancel_garbage(4);
+        assert_eq!(game.incoming, [1]);
+        assert_eq!(game.take_outgoing(), 0);
+        game.cancel_garbage(3);
+        assert!(game.incoming.is_empty());
+        assert_eq!(game.take_outgoing(), 2);
+    }
+
+    #[test]
+    fn cancelled_garbage_is_not_sent() {
+        let mut vertical = Tetromino::new(TetrominoKind::I);
+        vertical.rotate(Rotation::Clockwise);
+        let mut tetris = ready_to_drop(Mode::Versus, Delays::NONE, vertical);
+        let mut opponent = Game::with_seed(Mode::Versus, 43);
+        // a block left above the rows, for a tetris without a perfect clear
+        tetris.matrix[Matrix::HEIGHT - 5][9] = Some(Block::Piece(TetrominoKind::O, Default::default()));
+        tetris.receive(3);
+        tetris.update(Action::HardDrop);
+        assert_eq!(tetris.stats.lines, 4);
+        assert_eq!(tetris.stats.attack, 4);
+        versus::step([&mut tetris, &mut opponent]);
+        assert!(tetris.incoming.is_empty());
+        assert_eq!(tetris.matrix.garbage_rows(), 0);
+        assert_eq!(opponent.incoming, [1]);
+    }
+
+    #[test]
+    fn garbage_left_is_placed_without_a_clear() {
+        let mut game = Game::with_seed(Mode::Versus, 42);
+        game.receive(2);
+        game.update(Action::HardDrop);
+        assert!(game.incoming.is_empty());
+        assert_eq!(game.matrix.garbage_rows(), 2);
+    }
+
+    #[test]
+    fn tapping_to_the_wall_is_a_finesse_fault() {
+        let mut game = Game::with_seed(Mode::Endless, 42);
+        play(&mut game, &[Action::MoveLeft; Matrix::WIDTH]);
+        play(&mut game, &[Action::HardDrop]);
+        assert_eq!(game.stats.finesse_faults, 1);
+    }
+
+    #[test]
+    fn shifting_to_the_wall_is_no_finesse_fault() {
+        let mut game = Game::with_seed(Mode::Endless, 42);
+        game.update(Action::MoveLeft);
+        for _ in 0..Game::FPS {
+            game.step();
+        }
+        game.release(Action::MoveLeft);
+        assert!(!game.try_move(Direction::Left), "the auto shift reached the wall");
+        play(&mut game, &[Action::HardDrop]);
+        assert_eq!(game.stats.finesse_faults, 0);
+    }
+
+    #[test]
+    fn finesse_trainer_retries_a_fault() {
+        let mut game = Game::with_seed(Mode::Finesse, 42);
+        let kind = game.cur_tetromino.as_ref().unwrap().kind();
+        let target = game.target.clone().unwrap();
+        play(&mut game, &[Action::MoveLeft; Matrix::WIDTH]);
+        play(&mut game, &[Action::HardDrop]);
+        assert_eq!(game.stats.retries, 1);
+        assert_eq!(game.cur_tetromino.as_ref().unwrap().kind(), kind);
+        assert!(finesse::same_placement(game.target.as_ref().unwrap(), &target));
+        assert!(game.matrix.iter().flatten().all(Option::is_none));
+    }
+
+    #[test]
+    fn fnv1a_matches_the_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn seed_gives_the_same_pieces_everywhere() {
+        use TetrominoKind::*;
+        let game = Game::with_seed(Mode::Endless, 42);
+        let pieces: Vec<_> = game.cur_tetromino.iter().chain(game.bag.iter().rev()).map(Tetromino::kind).take(10).collect();
+        // changing the generator or the shuffle breaks the replays and the online games
+        assert_eq!(pieces, [S, O, J, Z, L, I, T, T, L, I]);
+    }
+
+    #[test]
+    fn same_games_have_the_same_checksum() {
+        use Action::*;
+        let actions = [MoveLeft, HardDrop, RotateClockwise, MoveRight, HardDrop, HardDrop];
+        let (mut a, mut b) = (Game::with_seed(Mode::Versus, 3), Game::with_seed(Mode::Versus, 3));
+        play(&mut a, &actions);
+        play(&mut b, &actions);
+        assert_eq!(a.checksum(), b.checksum());
+    }
+
+    #[test]
+    fn checksum_changes_with_the_state() {
+        let mut game = Game::with_seed(Mode::Versus, 3);
+        play(&mut game, &[Action::HardDrop]);
+        let before = game.checksum();
+
+        let mut moved = game.clone();
+        play(&mut moved, &[Action::MoveLeft]);
+        assert_ne!(moved.checksum(), before);
+
+        let mut attacked = game.clone();
+        attacked.receive(2);
+        assert_ne!(attacked.checksum(), before);
+
+        assert_ne!(Game::with_seed(Mode::Versus, 4).checksum(), Game::with_seed(Mode::Versus, 3).checksum());
+    }
+}
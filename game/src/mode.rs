@@ -0,0 +1,244 @@
+use crate::Game;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    time::Duration,
+};
+
+/// The modes a game can be played in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Plays until topping out.
+    #[default]
+    Endless,
+    /// Clears 40 lines as fast as possible.
+    Sprint,
+    /// Scores as many points as possible in 2 minutes.
+    Ultra,
+    /// Raises the level every 10 lines from `start_level`, until level 15 is cleared.
+    /// If `endless`, the game goes on until topping out.
+    Marathon {
+        start_level: u32,
+        endless: bool,
+    },
+    /// Clears every garbage row as fast as possible, starting with `rows` of them.
+    /// If `rising`, a new garbage row rises from the bottom every `Mode::RISE_INTERVAL`.
+    Dig {
+        rows: u32,
+        rising: bool,
+    },
+    /// Trains the finesse: each tetromino has a target placement on an empty matrix, and is tried
+    /// again if it misses it or takes more inputs than needed, until `Mode::FINESSE_PIECES` are
+    /// placed without a fault.
+    Finesse,
+    /// Plays against another player: clearing rows sends them garbage, the first to top out loses.
+    Versus,
+}
+
+impl Mode {
+    /// Lines cleared by each split of the timer.
+    pub const SPLIT_LINES: u32 = 10;
+    /// Lines cleared by each level.
+    pub const LEVEL_LINES: u32 = 10;
+    /// Last level of the marathon (unless it is endless).
+    pub const MAX_LEVEL: u32 = 15;
+    /// Level after which gravity doesn't get faster.
+    const MAX_GRAVITY_LEVEL: u32 = 20;
+    /// Most garbage rows a dig can start with.
+    pub const MAX_DIG_ROWS: u32 = 15;
+    /// Time between two rising garbage rows of the dig.
+    pub const RISE_INTERVAL: Duration = Duration::from_secs(5);
+    /// Tetrominos to place without a fault to complete the finesse trainer.
+    pub const FINESSE_PIECES: u32 = 40;
+
+    /// Returns the name of the mode (as given on the command line).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Endless => "endless",
+            Self::Sprint => "sprint",
+            Self::Ultra => "ultra",
+            Self::Marathon { .. } => "marathon",
+            Self::Dig { .. } => "dig",
+            Self::Finesse => "finesse",
+            Self::Versus => "versus",
+        }
+    }
+    /// Returns the lines to clear to complete the mode, `None` if it has no goal.
+    pub fn goal(&self) -> Option<u32> {
+        match self {
+            Self::Endless | Self::Ultra | Self::Marathon { endless: true, .. } | Self::Dig { .. } | Self::Finesse | Self::Versus => None,
+            Self::Sprint => Some(40),
+            Self::Marathon { start_level, endless: false } => {
+                Some((Self::MAX_LEVEL + 1).saturating_sub(*start_level) * Self::LEVEL_LINES)
+            }
+        }
+    }
+    /// Returns the level reached after clearing `lines` rows.
+    pub fn level(&self, lines: u32) -> u32 {
+        match self {
+            Self::Marathon { start_level, endless } => {
+                let level = start_level + lines / Self::LEVEL_LINES;
+                if *endless { level } else { level.min(Self::MAX_LEVEL) }
+            }
+            _ => 1,
+        }
+    }
+    /// Returns the time a tetromino takes to drop by a row at `level`.
+    ///
+    /// Only the marathon follows the guideline's curve, other modes keep `Game::BASE_TICK`.
+    pub fn gravity(&self, level: u32) -> Duration {
+        match self {
+            Self::Marathon { .. } => {
+                let level = level.clamp(1, Self::MAX_GRAVITY_LEVEL) as f64 - 1.0;
+                Duration::from_secs_f64((0.8 - level * 0.007).powf(level))
+            }
+            _ => Game::BASE_TICK,
+        }
+    }
+    /// Returns the duration of the mode, `None` if it isn't timed.
+    pub fn time_limit(&self) -> Option<Duration> {
+        match self {
+            Self::Endless | Self::Sprint | Self::Marathon { .. } | Self::Dig { .. } | Self::Finesse | Self::Versus => None,
+            Self::Ultra => Some(Duration::from_secs(120)),
+        }
+    }
+}
+
+impl Display for Mode {
+    /// Writes the mode with its options as they are given on the command line
+    /// (e.g. `marathon 5 endless`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        match self {
+            Self::Marathon { start_level, endless } => {
+                write!(f, " {start_level}")?;
+                if *endless {
+                    write!(f, " endless")?;
+                }
+            }
+            Self::Dig { rows, rising } => {
+                write!(f, " {rows}")?;
+                if *rising {
+                    write!(f, " rising")?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    /// Parses a mode written by `Display`, whose options can be left out.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let mode = match words.first().copied() {
+            None | Some("endless") => Self::Endless,
+            Some("sprint") => Self::Sprint,
+            Some("ultra") => Self::Ultra,
+            Some("marathon") => {
+                let start_level = match words.get(1) {
+                    Some(level) => level.parse().ok().filter(|level| (1..=Self::MAX_LEVEL).contains(level))
+                        .ok_or(format!("invalid start level: {level} (expected 1 to {})", Self::MAX_LEVEL))?,
+                    None => 1,
+                };
+                let endless = words.get(2) == Some(&"endless");
+                Self::Marathon { start_level, endless }
+            }
+            Some("dig") => {
+                let rows = match words.get(1) {
+                    Some(rows) => rows.parse().ok().filter(|rows| (1..=Self::MAX_DIG_ROWS).contains(rows))
+                        .ok_or(format!("invalid garbage rows: {rows} (expected 1 to {})", Self::MAX_DIG_ROWS))?,
+                    None => 10,
+                };
+                let rising = words.get(2) == Some(&"rising");
+                Self::Dig { rows, rising }
+            }
+            Some("finesse") => Self::Finesse,
+            Some("versus") => Self::Versus,
+            Some(other) => return Err(format!("unknown mode: {other} (expected `endless`, `sprint`, `ultra`, `marathon`, `dig`, `finesse` or `versus`)")),
+        };
+        Ok(mode)
+    }
+}
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The goal of the mode was reached, its time ran out or the opponent topped out.
+    Completed,
+    /// A tetromino was placed (or spawned) outside of the matrix.
+    ToppedOut,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns `duration` in milliseconds, rounded.
+    fn millis(duration: Duration) -> u128 {
+        (duration.as_secs_f64() * 1000.0).round() as u128
+    }
+
+    #[test]
+    fn level_rises_every_10_lines() {
+        let marathon = Mode::Marathon { start_level: 1, endless: false };
+        let levels: Vec<_> = [0, 9, 10, 19, 20, 145].map(|lines| marathon.level(lines)).into();
+        assert_eq!(levels, [1, 1, 2, 2, 3, 15]);
+        assert_eq!(Mode::Marathon { start_level: 5, endless: false }.level(25), 7);
+    }
+
+    #[test]
+    fn level_stops_at_the_last_one_unless_endless() {
+        assert_eq!(Mode::Marathon { start_level: 1, endless: false }.level(500), Mode::MAX_LEVEL);
+        assert_eq!(Mode::Marathon { start_level: 1, endless: true }.level(500), 51);
+        assert_eq!(Mode::Sprint.level(500), 1);
+    }
+
+    #[test]
+    fn marathon_clears_the_levels_left() {
+        for start_level in 1..=Mode::MAX_LEVEL {
+            let goal = (Mode::MAX_LEVEL + 1 - start_level) * Mode::LEVEL_LINES;
+            assert_eq!(Mode::Marathon { start_level, endless: false }.goal(), Some(goal));
+        }
+        assert_eq!(Mode::Marathon { start_level: 1, endless: false }.goal(), Some(150));
+        assert_eq!(Mode::Marathon { start_level: 15, endless: false }.goal(), Some(10));
+        assert_eq!(Mode::Marathon { start_level: 1, endless: true }.goal(), None);
+        assert_eq!(Mode::Sprint.goal(), Some(40));
+    }
+
+    #[test]
+    fn gravity_follows_the_curve() {
+        let marathon = Mode::Marathon { start_level: 1, endless: true };
+        let ticks: Vec<_> = [1, 2, 5, 10, 15].map(|level| millis(marathon.gravity(level))).into();
+        assert_eq!(ticks, [1000, 793, 355, 64, 7]);
+        assert_eq!(marathon.gravity(20).as_micros(), 455);
+        // gravity doesn't get faster after level 20
+        assert_eq!(marathon.gravity(30), marathon.gravity(20));
+        assert_eq!(marathon.gravity(0), marathon.gravity(1));
+    }
+
+    #[test]
+    fn other_modes_keep_the_base_gravity() {
+        for mode in [Mode::Endless, Mode::Sprint, Mode::Ultra, Mode::Versus] {
+            assert_eq!(mode.gravity(10), Game::BASE_TICK);
+        }
+    }
+    #[test]
+    fn ultra_ends_after_2_minutes() {
+        let mut game = Game::with_seed(Mode::Ultra, 42);
+        game.start();
+        let frames = 120 * Game::FPS as u32;
+        while game.stats.frames < frames - 1 {
+            // the tetromino doesn't fall, so that the game can't top out
+            game.since_drop = Duration::ZERO;
+            game.step();
+            assert_eq!(game.outcome, None, "frame {}", game.stats.frames);
+        }
+        game.step();
+        assert_eq!(game.stats.frames, frames);
+        assert_eq!(game.outcome, Some(Outcome::Completed));
+    }
+}
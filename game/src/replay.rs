@@ -0,0 +1,188 @@
+use crate::{Action, Delays, Game, Handling, Mode};
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+    str::FromStr,
+    time::Duration,
+};
+
+/// Version of the replay format.
+const VERSION: u32 = 2;
+
+/// Errors which can occur while reading a replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The replay was written by another version of the game.
+    UnsupportedVersion,
+    /// A line of the replay can't be read.
+    InvalidLine(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion => write!(f, "not a version {VERSION} replay"),
+            Self::InvalidLine(line) => write!(f, "invalid line in replay: {line:?}"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// The inputs of a single-player game, which plays it out again from its seed
+/// (a game is deterministic).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub mode: Mode,
+    pub seed: u64,
+    pub handling: Handling,
+    pub delays: Delays,
+    /// Each press (`true`) or release of an action, with the frame it was done on
+    /// (see `Stats::frames`), first to last.
+    pub inputs: Vec<(u32, Action, bool)>,
+    /// Final score of the game.
+    pub score: u32,
+    /// Frames the game lasted.
+    pub frames: u32,
+}
+
+impl Replay {
+    /// Starts recording the inputs of `game`, which shouldn't have any yet.
+    pub fn new(game: &Game) -> Self {
+        Self {
+            mode: game.mode,
+            seed: game.seed,
+            handling: game.handling,
+            delays: game.delays,
+            inputs: Vec::new(),
+            score: 0,
+            frames: 0,
+        }
+    }
+    /// Records a press (`pressed`) or a release of `action` done on `game`, on its current frame.
+    pub fn record(&mut self, game: &Game, action: Action, pressed: bool) {
+        self.inputs.push((game.stats.frames, action, pressed));
+    }
+    /// Records the result of the ended `game`.
+    pub fn finish(&mut self, game: &Game) {
+        self.score = game.score;
+        self.frames = game.stats.frames;
+    }
+    /// Returns the game at its start (started: its timer runs from the first frame).
+    pub fn game(&self) -> Game {
+        let mut game = Game::with_seed(self.mode, self.seed);
+        game.handling = self.handling;
+        game.delays = self.delays;
+        game.start();
+        game
+    }
+    /// Does on `game` the inputs of its current frame, from the `next` one (which is advanced).
+    ///
+    /// Should be called before each `Game::step`.
+    pub fn play(&self, game: &mut Game, next: &mut usize) {
+        while let Some(&(frame, action, pressed)) = self.inputs.get(*next) {
+            if frame > game.stats.frames {
+                break;
+            }
+            if pressed {
+                game.update(action);
+            } else {
+                game.release(action);
+            }
+            *next += 1;
+        }
+    }
+    /// Plays the whole replay at once.
+    /// # Returns
+    /// the game at its end (after its last input if the replay wasn't finished).
+    pub fn play_out(&self) -> Game {
+        let mut game = self.game();
+        let mut next = 0;
+        let end = self.frames.max(self.inputs.last().map_or(0, |&(frame, ..)| frame));
+        while game.outcome.is_none() && game.stats.frames < end {
+            self.play(&mut game, &mut next);
+            game.step();
+        }
+        game
+    }
+}
+
+impl Display for Replay {
+    /// Writes the replay as lines of text: a header then one input per line (`frame +L`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "REPLAY {VERSION}")?;
+        writeln!(f, "MODE {}", self.mode)?;
+        writeln!(f, "SEED {}", self.seed)?;
+        writeln!(f, "HANDLING {} {} {}", self.handling.das.as_nanos(), self.handling.arr.as_nanos(), self.handling.sdf)?;
+        writeln!(f, "DELAYS {} {}", self.delays.are, self.delays.line_clear)?;
+        writeln!(f, "RESULT {} {}", self.score, self.frames)?;
+        for (frame, action, pressed) in &self.inputs {
+            writeln!(f, "{frame} {}{}", if *pressed { '+' } else { '-' }, action.letter())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = Error;
+
+    /// Parses a replay written by `Display`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut lines = s.lines();
+        if lines.next() != Some(format!("REPLAY {VERSION}").as_str()) {
+            return Err(Error::UnsupportedVersion);
+        }
+        // the header's values after its keyword
+        let mut header = |keyword: &str| {
+            let line = lines.next().unwrap_or_default();
+            line.strip_prefix(keyword)
+                .and_then(|values| values.strip_prefix(' '))
+                .map(str::to_string)
+                .ok_or_else(|| Error::InvalidLine(line.to_string()))
+        };
+        let invalid = |line: &str| Error::InvalidLine(line.to_string());
+        let numbers = |line: &str| -> Result<Vec<u64>, Error> {
+            line.split_whitespace().map(|word| word.parse().map_err(|_| invalid(line))).collect()
+        };
+
+        let mode = header("MODE")?;
+        let mode = mode.parse().map_err(|_| invalid(&mode))?;
+        let seed = header("SEED")?;
+        let seed = seed.parse().map_err(|_| invalid(&seed))?;
+        let handling = header("HANDLING")?;
+        let [das, arr, sdf] = numbers(&handling)?[..] else { return Err(invalid(&handling)) };
+        let delays = header("DELAYS")?;
+        let [are, line_clear] = numbers(&delays)?[..] else { return Err(invalid(&delays)) };
+        let result = header("RESULT")?;
+        let [score, frames] = numbers(&result)?[..] else { return Err(invalid(&result)) };
+
+        let inputs = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (frame, input) = line.split_once(' ').ok_or_else(|| invalid(line))?;
+                let mut chars = input.chars();
+                let pressed = match chars.next() {
+                    Some('+') => true,
+                    Some('-') => false,
+                    _ => return Err(invalid(line)),
+                };
+                let action = chars.next().and_then(Action::from_letter).ok_or_else(|| invalid(line))?;
+                Ok((frame.parse().map_err(|_| invalid(line))?, action, pressed))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            mode,
+            seed,
+            handling: Handling {
+                das: Duration::from_nanos(das),
+                arr: Duration::from_nanos(arr),
+                sdf: sdf as u32,
+            },
+            delays: Delays { are: are as u32, line_clear: line_clear as u32 },
+            inputs,
+            score: score as u32,
+            frames: frames as u32,
+        })
+    }
+}
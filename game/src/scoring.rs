@@ -0,0 +1,93 @@
+//! Guideline scoring and attack table. Every point is multiplied by the level, except drops.
+
+/// Points for each cell a tetromino is soft dropped by.
+pub const SOFT_DROP: u32 = 1;
+/// Points for each cell a tetromino is hard dropped by.
+pub const HARD_DROP: u32 = 2;
+/// Points for each consecutive placement clearing rows (after the first one).
+pub const COMBO: u32 = 50;
+/// Garbage rows sent by each combo (capped to the last one).
+const COMBO_ATTACK: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+/// Garbage rows sent for emptying the matrix.
+const PERFECT_CLEAR_ATTACK: u32 = 10;
+
+/// Returns the points of a placement which cleared `lines` rows.
+pub fn clear_points(lines: u32, t_spin: bool) -> u32 {
+    match (t_spin, lines) {
+        (false, 0) => 0,
+        (false, 1) => 100,
+        (false, 2) => 300,
+        (false, 3) => 500,
+        (false, _) => 800,
+        (true, 0) => 400,
+        (true, 1) => 800,
+        (true, 2) => 1200,
+        (true, _) => 1600,
+    }
+}
+
+/// Returns `true` if the clear is difficult: consecutive difficult clears are worth 50% more
+/// (back-to-back).
+#[inline]
+pub fn is_difficult(lines: u32, t_spin: bool) -> bool {
+    lines >= 4 || (t_spin && lines > 0)
+}
+
+/// Returns the garbage rows sent by a placement which cleared `lines` rows.
+pub fn attack(lines: u32, t_spin: bool, back_to_back: bool, combo: u32, perfect_clear: bool) -> u32 {
+    let base = match (t_spin, lines) {
+        (_, 0) => return 0,
+        (false, 1) => 0,
+        (false, 2) => 1,
+        (false, 3) => 2,
+        (false, _) => 4,
+        (true, 1) => 2,
+        (true, 2) => 4,
+        (true, _) => 6,
+    };
+    base
+        + back_to_back as u32
+        + COMBO_ATTACK[(combo as usize).min(COMBO_ATTACK.len() - 1)]
+        + if perfect_clear { PERFECT_CLEAR_ATTACK } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clears_send_the_rows_of_the_table() {
+        let sent: Vec<_> = (0..=4).map(|lines| attack(lines, false, false, 0, false)).collect();
+        assert_eq!(sent, [0, 0, 1, 2, 4]);
+        let sent: Vec<_> = (0..=3).map(|lines| attack(lines, true, false, 0, false)).collect();
+        assert_eq!(sent, [0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn back_to_back_sends_a_row_more() {
+        assert_eq!(attack(4, false, true, 0, false), 5);
+        assert_eq!(attack(2, true, true, 0, false), 5);
+    }
+
+    #[test]
+    fn combos_send_rows_up_to_the_last_of_the_table() {
+        let sent: Vec<_> = (0..12).map(|combo| attack(1, false, false, combo, false)).collect();
+        assert_eq!(sent, COMBO_ATTACK);
+        assert_eq!(attack(1, false, false, 50, false), 5);
+        assert_eq!(attack(2, false, false, 3, false), 2);
+    }
+
+    #[test]
+    fn perfect_clears_send_ten_rows_more() {
+        assert_eq!(attack(1, false, false, 0, true), 10);
+        assert_eq!(attack(4, false, true, 2, true), 16);
+    }
+
+    #[test]
+    fn difficult_clears_are_tetrises_and_t_spins() {
+        assert!(is_difficult(4, false));
+        assert!(is_difficult(1, true));
+        assert!(!is_difficult(3, false));
+        assert!(!is_difficult(0, true));
+    }
+}
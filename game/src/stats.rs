@@ -0,0 +1,122 @@
+use crate::Game;
+use pieces::TetrominoKind;
+use std::time::Duration;
+
+/// Statistics of a game.
+#[derive(Clone, Default)]
+pub struct Stats {
+    /// Frames since the timer started.
+    pub frames: u32,
+    /// Tetrominos placed.
+    pub pieces: u32,
+    /// Tetrominos placed of each kind, in the order of `TetrominoKind::ALL`.
+    pub distribution: [u32; 7],
+    /// Rows cleared.
+    pub lines: u32,
+    /// Garbage rows sent (cancelled ones included).
+    pub attack: u32,
+    /// T-Spins placed, with or without rows cleared.
+    pub t_spins: u32,
+    /// Keys pressed to play.
+    pub keys: u32,
+    /// Tetrominos placed with more moves and rotations than needed.
+    pub finesse_faults: u32,
+    /// Tetrominos tried again in the finesse trainer, after a fault or a missed target.
+    pub retries: u32,
+    /// Time at which every `Mode::SPLIT_LINES` lines were cleared.
+    pub splits: Vec<Duration>,
+}
+
+impl Stats {
+    /// Returns the time since the timer started.
+    #[inline]
+    pub fn time(&self) -> Duration {
+        Duration::from_nanos(self.frames as u64 * 1_000_000_000 / Game::FPS)
+    }
+    /// Returns the pieces placed per second.
+    pub fn pps(&self) -> f64 {
+        match self.time().as_secs_f64() {
+            0.0 => 0.0,
+            secs => self.pieces as f64 / secs,
+        }
+    }
+    /// Returns the rows cleared per minute.
+    pub fn lpm(&self) -> f64 {
+        self.per_minute(self.lines)
+    }
+    /// Returns the garbage rows sent per minute (APM).
+    pub fn apm(&self) -> f64 {
+        self.per_minute(self.attack)
+    }
+    /// Returns the tetrominos of `kind` placed.
+    pub fn placed(&self, kind: TetrominoKind) -> u32 {
+        self.distribution[kind.index()]
+    }
+    /// Returns `count` per minute since the timer started.
+    fn per_minute(&self, count: u32) -> f64 {
+        match self.time().as_secs_f64() {
+            0.0 => 0.0,
+            secs => count as f64 * 60.0 / secs,
+        }
+    }
+    /// Returns the keys pressed per piece placed.
+    pub fn kpp(&self) -> f64 {
+        match self.pieces {
+            0 => 0.0,
+            pieces => self.keys as f64 / pieces as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Mode};
+
+    #[test]
+    fn rates_are_zero_at_the_start() {
+        let stats = Stats { pieces: 3, lines: 2, attack: 4, keys: 5, ..Stats::default() };
+        assert_eq!(stats.time(), Duration::ZERO);
+        assert_eq!(stats.pps(), 0.0);
+        assert_eq!(stats.lpm(), 0.0);
+        assert_eq!(stats.apm(), 0.0);
+        assert_eq!(Stats { keys: 5, ..Stats::default() }.kpp(), 0.0);
+    }
+
+    #[test]
+    fn rates_are_per_second_minute_and_piece() {
+        let stats = Stats {
+            frames: 30 * Game::FPS as u32,
+            pieces: 60,
+            lines: 20,
+            attack: 12,
+            keys: 150,
+            ..Stats::default()
+        };
+        assert_eq!(stats.time(), Duration::from_secs(30));
+        assert_eq!(stats.pps(), 2.0);
+        assert_eq!(stats.lpm(), 40.0);
+        assert_eq!(stats.apm(), 24.0);
+        assert_eq!(stats.kpp(), 2.5);
+    }
+
+    #[test]
+    fn placements_with_extra_inputs_are_finesse_faults() {
+        let mut game = Game::with_seed(Mode::Endless, 42);
+        // a tap, then taps to the wall (a single input with the auto shift)
+        for actions in [[Action::MoveLeft].as_slice(), &[Action::MoveLeft; 4]] {
+            for &action in actions.iter().chain([&Action::HardDrop]) {
+                while game.cur_tetromino.is_none() {
+                    game.step();
+                }
+                game.update(action);
+                game.release(action);
+                game.step();
+            }
+        }
+        assert_eq!(game.stats.pieces, 2);
+        assert_eq!(game.stats.finesse_faults, 1);
+        assert_eq!(game.stats.keys, 7);
+        assert_eq!(game.stats.kpp(), 3.5);
+    }
+}
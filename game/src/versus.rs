@@ -0,0 +1,34 @@
+use crate::{Game, Outcome};
+
+/// Starts every game of a versus once one of them started (with the first input of a player).
+pub fn start<'a>(games: impl IntoIterator<Item = &'a mut Game>) {
+    let mut games: Vec<_> = games.into_iter().collect();
+    if games.iter().any(|game| game.started) {
+        games.iter_mut().for_each(|game| game.start());
+    }
+}
+
+/// Simulates a frame of a versus: steps every game, delivers the garbage each one sent to the
+/// other ones, and makes the last players standing win once one tops out.
+///
+/// The result doesn't depend on the order of `games`, so peers of an online versus can each
+/// put their own game first.
+pub fn step<'a>(games: impl IntoIterator<Item = &'a mut Game>) {
+    let mut games: Vec<_> = games.into_iter().collect();
+    for game in &mut games {
+        game.step();
+    }
+    let outgoing: Vec<u32> = games.iter_mut().map(|game| game.take_outgoing()).collect();
+    for (i, game) in games.iter_mut().enumerate() {
+        for (j, rows) in outgoing.iter().enumerate() {
+            if j != i {
+                game.receive(*rows);
+            }
+        }
+    }
+    if games.iter().any(|game| game.outcome == Some(Outcome::ToppedOut)) {
+        for game in &mut games {
+            game.outcome.get_or_insert(Outcome::Completed);
+        }
+    }
+}
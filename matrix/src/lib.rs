@@ -1,7 +1,7 @@
 //! # Matrix
 //! Contains the struct def of the game's matrix
 
-use pieces::Tetromino;
+use pieces::{Tetromino, TetrominoKind};
 use vecmath::Point;
 use std::{
     ops::{
@@ -9,14 +9,61 @@ use std::{
         IndexMut,
     },
     default::Default,
-    process,
 };
-use sdl2::pixels::Color;
 
 /// 10*20 2D array.
 /// 
-/// Each cell is either empty (`None`) or filled with a piece (`Some<Color>`)
-type Grid = [[Option<Color>; Matrix::WIDTH]; Matrix::HEIGHT];
+/// Each cell is either empty (`None`) or filled with a block (`Some<Block>`)
+type Grid = [[Option<Block>; Matrix::WIDTH]; Matrix::HEIGHT];
+
+/// A filled cell of the matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Block {
+    /// A block left by a placed tetromino, joined to the other blocks left by it.
+    Piece(TetrominoKind, Links),
+    /// A gray block which doesn't belong to any tetromino.
+    Garbage,
+}
+
+impl Block {
+    /// Returns the sides of `self` joined to other blocks (none for garbage).
+    #[inline(always)]
+    pub fn links(&self) -> Links {
+        match self {
+            Self::Piece(_, links) => *links,
+            Self::Garbage => Links::default(),
+        }
+    }
+}
+
+/// The sides of a block joined to another block of the same tetromino (to draw them as one shape).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Links(u8);
+
+impl Links {
+    pub const UP: Self = Self(1);
+    pub const RIGHT: Self = Self(2);
+    pub const DOWN: Self = Self(4);
+    pub const LEFT: Self = Self(8);
+
+    /// Returns the sides of the block at `cell` joined to the other ones of `cells`.
+    pub fn between(cell: Point, cells: &[Point]) -> Self {
+        [(Self::UP, (0, -1)), (Self::RIGHT, (1, 0)), (Self::DOWN, (0, 1)), (Self::LEFT, (-1, 0))]
+            .into_iter()
+            .filter(|&(_, offset)| cells.contains(&(cell + Point::from(offset))))
+            .fold(Self::default(), |links, (side, _)| Self(links.0 | side.0))
+    }
+    /// Returns the sides as bits (`UP` 1, `RIGHT` 2, `DOWN` 4, `LEFT` 8).
+    #[inline(always)]
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+    /// Returns `self` without `side`.
+    #[inline(always)]
+    fn without(self, side: Self) -> Self {
+        Self(self.0 & !side.0)
+    }
+}
 
 /// The game's matrix.
 /// 
@@ -25,7 +72,7 @@ type Grid = [[Option<Color>; Matrix::WIDTH]; Matrix::HEIGHT];
 pub struct Matrix(Grid);
 
 impl Index<Point> for Matrix {
-    type Output = Option<Color>;
+    type Output = Option<Block>;
     #[inline]
     fn index(&self, index: Point) -> &Self::Output {
         assert!(Self::in_bounds(index), "value of (x, y): {}", index);
@@ -42,7 +89,7 @@ impl IndexMut<Point> for Matrix {
 }
 
 impl Index<usize> for Matrix {
-    type Output = [Option<Color>; Self::WIDTH];
+    type Output = [Option<Block>; Self::WIDTH];
     fn index(&self, index: usize) -> &Self::Output {
         &self.0[index]
     }
@@ -62,29 +109,74 @@ impl Matrix {
     /// 
     /// The iterator yields all items from start to end.
     #[inline(always)]
-    pub fn iter(&self) -> core::slice::Iter<[Option<Color>; Self::WIDTH]> {
+    pub fn iter(&self) -> core::slice::Iter<'_, [Option<Block>; Self::WIDTH]> {
         self.0.iter()
     }
     
     /// Increases by 1 each cell's y that is lower than `index`.
     pub fn push_down(&mut self, index: usize) {
+        // the blocks around the row removed aren't joined to it anymore
+        if index > 0 {
+            self.unlink(index - 1, Links::DOWN);
+        }
+        if index + 1 < Self::HEIGHT {
+            self.unlink(index + 1, Links::UP);
+        }
         for i in (1..=index).rev() {
             self[i] = self[i - 1];
         }
+        self[0] = Default::default();
     }
     
-    /// Takes a piece and place it into the matrix.
-    /// # Errors
-    /// if the place cannot be placed, it ends the game stating the player lost.
-    pub fn place_piece(&mut self, tetromino: Tetromino) {
-        for coord in tetromino.position() {
-            if self[coord].is_some() {
-                println!("You lost.");
-                process::exit(0);
+    /// Decreases by 1 each cell's y and inserts `row` at the bottom (the reverse of `push_down`).
+    /// # Returns
+    /// `false` if a block was pushed out of the top of the matrix, `true` otherwise.
+    pub fn push_up(&mut self, row: [Option<Block>; Self::WIDTH]) -> bool {
+        let fits = self[0].iter().all(Option::is_none);
+        self.unlink(1, Links::UP);
+        for i in 0..Self::HEIGHT - 1 {
+            self[i] = self[i + 1];
+        }
+        self[Self::HEIGHT - 1] = row;
+        fits
+    }
+    
+    /// Removes `side` from the links of the blocks of the row `index`.
+    fn unlink(&mut self, index: usize, side: Links) {
+        for block in &mut self[index] {
+            if let Some(Block::Piece(_, links)) = block {
+                *links = links.without(side);
             }
-            self[coord] = Some(tetromino.get_color());
         }
     }
+    
+    /// Returns a row of garbage with only the cell at `hole` empty.
+    pub fn garbage_row(hole: usize) -> [Option<Block>; Self::WIDTH] {
+        let mut row = [Some(Block::Garbage); Self::WIDTH];
+        row[hole] = None;
+        row
+    }
+    
+    /// Returns the number of rows which contain garbage.
+    pub fn garbage_rows(&self) -> usize {
+        self.iter()
+            .filter(|row| row.contains(&Some(Block::Garbage)))
+            .count()
+    }
+    
+    /// Takes a piece and place it into the matrix.
+    /// # Returns
+    /// `false` (and the matrix is left as it was) if the piece overlaps a block.
+    pub fn place_piece(&mut self, tetromino: Tetromino) -> bool {
+        let position = tetromino.position();
+        if position.iter().any(|&coord| self[coord].is_some()) {
+            return false;
+        }
+        for coord in position {
+            self[coord] = Some(Block::Piece(tetromino.kind(), Links::between(coord, &position)));
+        }
+        true
+    }
     /// Returns `true` if the given coordinate is in the bounds of the matrix, `false` otherwise.
     #[inline]
     fn in_bounds(coord: Point) -> bool {
@@ -96,15 +188,70 @@ impl Matrix {
     /// Returns `true` if each coordinate of a given piece is **not** in the bounds of the matrix
     /// or if it is already occupied,
     /// `false` otherwise.
+    ///
+    /// Cells above the matrix are free (tetrominos spawn partially above it).
     pub fn will_collide(&self, tetromino: &Tetromino) -> bool {
-        for coord in tetromino.position() {
-            if !Self::in_bounds(coord) {
-                return true;
-            }
-            if self[coord].is_some() {
-                return true;
+        tetromino.position().into_iter().any(|coord| self.is_occupied(coord))
+    }
+    /// Returns `true` if the given coordinate is filled or beside/below the matrix,
+    /// `false` if it is empty or above it.
+    pub fn is_occupied(&self, coord: Point) -> bool {
+        if coord.y < 0 && coord.x >= 0 && coord.x < Self::WIDTH as isize {
+            return false;
+        }
+        !Self::in_bounds(coord) || self[coord].is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn garbage_row_has_its_hole_at_the_column() {
+        for hole in 0..Matrix::WIDTH {
+            let row = Matrix::garbage_row(hole);
+            for (x, cell) in row.iter().enumerate() {
+                assert_eq!(cell.is_none(), x == hole, "hole {hole}, column {x}");
             }
+            assert!(row.iter().flatten().all(|&block| block == Block::Garbage));
+        }
+    }
+
+    #[test]
+    fn rows_are_pushed_up_from_the_bottom() {
+        let mut matrix = Matrix::default();
+        assert!(matrix.push_up(Matrix::garbage_row(2)));
+        assert!(matrix.push_up(Matrix::garbage_row(7)));
+        assert_eq!(matrix[Matrix::HEIGHT - 1], Matrix::garbage_row(7));
+        assert_eq!(matrix[Matrix::HEIGHT - 2], Matrix::garbage_row(2));
+        assert_eq!(matrix.garbage_rows(), 2);
+    }
+
+    #[test]
+    fn pushing_a_block_out_of_the_top_overflows() {
+        let mut matrix = Matrix::default();
+        for i in 0..Matrix::HEIGHT {
+            assert!(matrix.push_up(Matrix::garbage_row(i % Matrix::WIDTH)), "row {i} fits");
+        }
+        assert_eq!(matrix.garbage_rows(), Matrix::HEIGHT);
+        // the top row is full: pushing another one loses it
+        assert!(!matrix.push_up(Matrix::garbage_row(0)));
+        assert_eq!(matrix[0], Matrix::garbage_row(1));
+        assert_eq!(matrix[Matrix::HEIGHT - 1], Matrix::garbage_row(0));
+    }
+
+    #[test]
+    fn pushing_up_unlinks_the_pieces_from_the_lost_row() {
+        let mut matrix = Matrix::default();
+        // a vertical I in the first column, from the top row down
+        let cells = [(0, 0), (0, 1), (0, 2), (0, 3)].map(Point::from);
+        for cell in cells {
+            matrix[cell] = Some(Block::Piece(TetrominoKind::I, Links::between(cell, &cells)));
         }
-        false
+        assert!(!matrix.push_up(Matrix::garbage_row(0)));
+        assert_eq!(matrix[0][0].unwrap().links(), Links::DOWN);
+        assert_eq!(matrix[2][0].unwrap().links(), Links::UP);
+        assert_eq!(matrix.garbage_rows(), 1);
     }
 }
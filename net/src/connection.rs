@@ -0,0 +1,94 @@
+use crate::{Error, Simulation, protocol::Message};
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Write},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A non blocking TCP connection which sends and receives messages one per line.
+pub(crate) struct Connection {
+    stream: TcpStream,
+    /// Bytes received which don't make a whole line yet.
+    received: Vec<u8>,
+    /// Bytes which couldn't be sent yet.
+    unsent: Vec<u8>,
+    /// Network conditions simulated on the messages sent.
+    simulation: Option<Simulation>,
+    /// Messages held back by the `simulation`, with the time to send them at.
+    delayed: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl Connection {
+    pub(crate) fn new(stream: TcpStream, simulation: Option<Simulation>) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            stream,
+            received: Vec::new(),
+            unsent: Vec::new(),
+            simulation,
+            delayed: VecDeque::new(),
+        })
+    }
+    /// Queues `message` and sends as much as possible of the queued ones.
+    pub(crate) fn send(&mut self, message: &Message) -> Result<(), Error> {
+        let bytes = format!("{message}\n").into_bytes();
+        match self.simulation {
+            // messages stay in order, as they would on TCP
+            Some(simulation) => {
+                let at = (Instant::now() + simulation.delay()).max(self.delayed.back().map_or(Instant::now(), |(at, _)| *at));
+                self.delayed.push_back((at, bytes));
+            }
+            None => self.unsent.extend_from_slice(&bytes),
+        }
+        self.flush()
+    }
+    /// Sends as much as possible of the queued messages without blocking.
+    pub(crate) fn flush(&mut self) -> Result<(), Error> {
+        while self.delayed.front().is_some_and(|(at, _)| *at <= Instant::now()) {
+            let (_, bytes) = self.delayed.pop_front().unwrap();
+            self.unsent.extend_from_slice(&bytes);
+        }
+        while !self.unsent.is_empty() {
+            match self.stream.write(&self.unsent) {
+                Ok(0) => return Err(Error::Disconnected),
+                Ok(n) => {
+                    self.unsent.drain(..n);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+    /// Returns the next message received, `None` if there isn't a whole one yet.
+    pub(crate) fn receive(&mut self) -> Result<Option<Message>, Error> {
+        loop {
+            if let Some(end) = self.received.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.received.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                return Message::parse(line.trim_end()).map(Some);
+            }
+            let mut buffer = [0; 1024];
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(Error::Disconnected),
+                Ok(n) => self.received.extend_from_slice(&buffer[..n]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    /// Waits for the next message.
+    pub(crate) fn wait(&mut self) -> Result<Message, Error> {
+        loop {
+            if let Some(message) = self.receive()? {
+                return Ok(message);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
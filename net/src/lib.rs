@@ -0,0 +1,518 @@
+//! # Net
+//! Online versus over TCP: one peer hosts, the other joins.
+//!
+//! The peers exchange their inputs frame by frame and each one simulates both games
+//! (the games are seeded the same way and the peers send each other their handling), so garbage
+//! doesn't need to be sent. The inputs are played a few frames after they are pressed (the input
+//! delay) to hide part of the latency.
+//!
+//! Until the inputs of the other player arrive, they are predicted (no new press or release)
+//! and the games are simulated ahead of them, up to the rollback window. When they arrive and
+//! differ from the prediction, the games are restored from the last snapshot where the inputs
+//! of both players were known, and simulated again. The checksums of those snapshots are compared
+//! every second to detect desyncs.
+
+/// Module to send and receive messages over TCP
+mod connection;
+/// Module of the messages between the peers
+mod protocol;
+/// Module to simulate latency and packet loss
+mod simulation;
+
+pub use protocol::Input;
+pub use simulation::Simulation;
+
+use connection::Connection;
+use game::{Action, Delays, Game, Handling};
+use protocol::{Message, VERSION};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error as StdError,
+    fmt::{self, Display},
+    io,
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+/// Port used when the address doesn't have one.
+pub const DEFAULT_PORT: u16 = 7878;
+/// Frames between an input and the frame it is played at, when not chosen by the host.
+pub const DEFAULT_DELAY: u32 = 2;
+/// Frames the games can be simulated ahead of the inputs of the other player, when not chosen.
+pub const DEFAULT_WINDOW: u32 = 8;
+/// Frames between two checksums.
+const CHECKSUM_INTERVAL: u32 = Game::FPS as u32;
+
+/// Errors which can occur during an online versus.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The other peer closed the connection.
+    Disconnected,
+    /// The other peer sent something which isn't a message (or not the expected one).
+    InvalidMessage(String),
+    /// The other peer uses another version of the protocol.
+    VersionMismatch(u32),
+    /// The games of the peers differ since the given frame.
+    Desync(u32),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Disconnected => write!(f, "the other player disconnected"),
+            Self::InvalidMessage(message) => write!(f, "invalid message: {message:?}"),
+            Self::VersionMismatch(version) => {
+                write!(f, "the other player uses version {version} of the protocol (expected {VERSION})")
+            }
+            Self::Desync(frame) => write!(f, "the games desynced at frame {frame}"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::BrokenPipe => {
+                Self::Disconnected
+            }
+            _ => Self::Io(err),
+        }
+    }
+}
+
+/// Options of a session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// Frames between an input and the frame it is played at (the host's one is used).
+    pub delay: u32,
+    /// Frames the games can be simulated ahead of the inputs of the other player
+    /// (0 waits for them, as lockstep does).
+    pub window: u32,
+    /// Network conditions simulated on the messages sent, `None` to send them as they are.
+    pub simulation: Option<Simulation>,
+    /// Handling of the local player (sent to the other peer, which simulates its game too).
+    pub handling: Handling,
+    /// Delays of both games (the host's ones are used).
+    pub delays: Delays,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            delay: DEFAULT_DELAY,
+            window: DEFAULT_WINDOW,
+            simulation: None,
+            handling: Handling::default(),
+            delays: Delays::default(),
+        }
+    }
+}
+
+/// The connection with the other peer, the inputs of both players and the last snapshot
+/// of the games where they were all known.
+pub struct Session {
+    connection: Connection,
+    /// Seed of both games.
+    pub seed: u64,
+    /// Frames between an input and the frame it is played at.
+    pub delay: u32,
+    /// Frames the games can be simulated ahead of the `confirmed` one.
+    pub window: u32,
+    /// Frame of the `snapshot`, the first one whose remote inputs aren't known.
+    confirmed: u32,
+    /// The local and the remote game at the `confirmed` frame.
+    snapshot: [Game; 2],
+    /// Frame the games shown are at.
+    frame: u32,
+    /// Local inputs since the last frame sent.
+    pending: Vec<Input>,
+    /// Local inputs of each frame from `confirmed`.
+    local: VecDeque<Vec<Input>>,
+    /// Remote inputs of each frame from `confirmed`.
+    remote: VecDeque<Vec<Input>>,
+    /// `true` if remote inputs were received for a frame which was simulated with a prediction.
+    mispredicted: bool,
+    /// Checksums of the remote game computed here, by frame.
+    checksums: HashMap<u32, u64>,
+    /// Checksums of the remote game received from the other peer, by frame.
+    received: HashMap<u32, u64>,
+    /// Frames simulated again after a misprediction, since the start.
+    pub rollback_frames: u64,
+}
+
+impl Session {
+    /// Waits for a player to join on `address`, then chooses the seed of the games.
+    pub fn host(address: impl ToSocketAddrs, options: Options) -> Result<Self, Error> {
+        let listener = TcpListener::bind(address)?;
+        let (stream, _) = listener.accept()?;
+        let mut connection = Connection::new(stream, options.simulation)?;
+        let seed = rand::random();
+        connection.send(&Message::Hello { version: VERSION, seed, delay: options.delay, delays: options.delays })?;
+        let remote = Self::exchange_handling(&mut connection, options.handling)?;
+        Ok(Self::new(connection, seed, options, remote))
+    }
+    /// Joins the player hosting on `address` (with the input delay and the delays of the host).
+    pub fn join(address: impl ToSocketAddrs, options: Options) -> Result<Self, Error> {
+        let mut connection = Connection::new(TcpStream::connect(address)?, options.simulation)?;
+        match connection.wait()? {
+            Message::Hello { version, seed, delay, delays } if version == VERSION => {
+                let remote = Self::exchange_handling(&mut connection, options.handling)?;
+                Ok(Self::new(connection, seed, Options { delay, delays, ..options }, remote))
+            }
+            Message::Hello { version, .. } => Err(Error::VersionMismatch(version)),
+            message => Err(Error::InvalidMessage(message.to_string())),
+        }
+    }
+    /// Sends the handling of the local player and waits for the one of the other player.
+    fn exchange_handling(connection: &mut Connection, local: Handling) -> Result<Handling, Error> {
+        connection.send(&Message::Handling(local))?;
+        match connection.wait()? {
+            Message::Handling(remote) => Ok(remote),
+            message => Err(Error::InvalidMessage(message.to_string())),
+        }
+    }
+    fn new(connection: Connection, seed: u64, options: Options, remote_handling: Handling) -> Self {
+        // nobody can press anything during the first frames
+        let empty: VecDeque<_> = (0..options.delay).map(|_| Vec::new()).collect();
+        let mut snapshot = [Game::with_seed(game::Mode::Versus, seed), Game::with_seed(game::Mode::Versus, seed)];
+        snapshot[0].handling = options.handling;
+        snapshot[1].handling = remote_handling;
+        for game in &mut snapshot {
+            game.delays = options.delays;
+        }
+        Self {
+            connection,
+            seed,
+            delay: options.delay,
+            window: options.window,
+            confirmed: 0,
+            snapshot,
+            frame: 0,
+            pending: Vec::new(),
+            local: empty.clone(),
+            remote: empty,
+            mispredicted: false,
+            checksums: HashMap::new(),
+            received: HashMap::new(),
+            rollback_frames: 0,
+        }
+    }
+    /// Returns the two games at the start of the session: the local one and the remote one.
+    pub fn games(&self) -> [Game; 2] {
+        self.snapshot.clone()
+    }
+    /// Handles a press of the key bound to `action` by the local player.
+    pub fn press(&mut self, action: Action) {
+        self.pending.push((action, true));
+    }
+    /// Handles a release of the key bound to `action` by the local player.
+    pub fn release(&mut self, action: Action) {
+        self.pending.push((action, false));
+    }
+    /// Returns the frames the games are simulated ahead of the inputs of the other player.
+    pub fn ahead(&self) -> u32 {
+        self.frame.saturating_sub(self.confirmed)
+    }
+    /// Sends the local inputs for a new frame, unless the games are already `window` frames
+    /// ahead of the inputs of the other player (then waiting for them).
+    ///
+    /// Should be called once per `Game::FRAME`.
+    pub fn tick(&mut self) -> Result<(), Error> {
+        // the frames shown are up to `delay` frames before the last one with local inputs
+        if self.local.len() as u32 <= self.window + self.delay {
+            let frame = self.confirmed + self.local.len() as u32;
+            let inputs = std::mem::take(&mut self.pending);
+            self.connection.send(&Message::Inputs { frame, inputs: inputs.clone() })?;
+            self.local.push_back(inputs);
+        }
+        self.connection.flush()
+    }
+    /// Receives the messages of the other peer and simulates `local` and `remote` up to
+    /// the last frame with local inputs (predicting the remote ones), rolling them back
+    /// if a prediction was wrong.
+    pub fn advance(&mut self, local: &mut Game, remote: &mut Game) -> Result<(), Error> {
+        while let Some(message) = self.connection.receive()? {
+            match message {
+                Message::Inputs { frame, inputs } if frame == self.confirmed + self.remote.len() as u32 => {
+                    if frame < self.frame && !inputs.is_empty() {
+                        self.mispredicted = true;
+                    }
+                    self.remote.push_back(inputs);
+                }
+                Message::Checksum { frame, checksum } => {
+                    self.received.insert(frame, checksum);
+                    self.check(frame)?;
+                }
+                message => return Err(Error::InvalidMessage(message.to_string())),
+            }
+        }
+
+        // move the snapshot to the last frame with the inputs of both players
+        while !self.local.is_empty() && !self.remote.is_empty() {
+            let [snapshot_local, snapshot_remote] = &mut self.snapshot;
+            simulate(snapshot_local, snapshot_remote, &self.local.pop_front().unwrap(), &self.remote.pop_front().unwrap());
+            self.confirmed += 1;
+            if self.confirmed.is_multiple_of(CHECKSUM_INTERVAL) {
+                self.connection.send(&Message::Checksum { frame: self.confirmed, checksum: snapshot_local.checksum() })?;
+                self.checksums.insert(self.confirmed, snapshot_remote.checksum());
+                self.check(self.confirmed)?;
+            }
+        }
+
+        // roll back to the snapshot
+        if self.mispredicted || self.frame < self.confirmed {
+            if self.mispredicted {
+                self.rollback_frames += (self.frame - self.confirmed.min(self.frame)) as u64;
+            }
+            [*local, *remote] = self.snapshot.clone();
+            self.frame = self.confirmed;
+            self.mispredicted = false;
+        }
+
+        // simulate ahead, predicting that the remote player doesn't press nor release anything
+        let target = (self.confirmed + self.local.len() as u32)
+            .saturating_sub(self.delay)
+            .min(self.confirmed + self.window);
+        while self.frame < target {
+            let i = (self.frame - self.confirmed) as usize;
+            let predicted = Vec::new();
+            simulate(local, remote, &self.local[i], self.remote.get(i).unwrap_or(&predicted));
+            self.frame += 1;
+        }
+        Ok(())
+    }
+    /// Compares the checksums of the remote game at `frame` once both are known.
+    fn check(&mut self, frame: u32) -> Result<(), Error> {
+        if let (Some(checksum), Some(received)) = (self.checksums.get(&frame), self.received.get(&frame)) {
+            if checksum != received {
+                return Err(Error::Desync(frame));
+            }
+            self.checksums.remove(&frame);
+            self.received.remove(&frame);
+        }
+        Ok(())
+    }
+}
+
+/// Simulates a frame of the versus between `local` and `remote` with their inputs.
+fn simulate(local: &mut Game, remote: &mut Game, local_inputs: &[Input], remote_inputs: &[Input]) {
+    play(local, local_inputs);
+    play(remote, remote_inputs);
+    game::versus::start([&mut *local, &mut *remote]);
+    game::versus::step([&mut *local, &mut *remote]);
+}
+
+/// Plays the inputs of a frame on `game`.
+fn play(game: &mut Game, inputs: &[Input]) {
+    for (action, pressed) in inputs {
+        if *pressed {
+            game.update(*action);
+        } else {
+            game.release(*action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    /// A peer with the games it shows.
+    struct Peer {
+        session: Session,
+        local: Game,
+        remote: Game,
+    }
+
+    impl Peer {
+        fn new(session: Session) -> Self {
+            let [local, remote] = session.games();
+            Self { session, local, remote }
+        }
+        /// Plays `inputs` on a new frame.
+        fn frame(&mut self, inputs: &[Input]) -> Result<(), Error> {
+            for &(action, pressed) in inputs {
+                if pressed {
+                    self.session.press(action);
+                } else {
+                    self.session.release(action);
+                }
+            }
+            self.session.tick()?;
+            self.session.advance(&mut self.local, &mut self.remote)
+        }
+        /// Returns the frames whose local inputs were sent.
+        fn sent(&self) -> u32 {
+            self.session.confirmed + self.session.local.len() as u32
+        }
+    }
+
+    /// Returns a host and a guest connected on localhost, with `options` and the seed 1.
+    fn pair(options: Options) -> (Peer, Peer) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let guest = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (host, _) = listener.accept().unwrap();
+        let session = |stream| Session::new(Connection::new(stream, None).unwrap(), 1, options, options.handling);
+        (Peer::new(session(host)), Peer::new(session(guest)))
+    }
+
+    /// Returns the inputs of the frame `i` of a player pressing `actions` in turn, a frame each,
+    /// every `period` frames.
+    fn inputs(i: u32, period: u32, actions: &[Action]) -> Vec<Input> {
+        let action = actions[(i / period) as usize % actions.len()];
+        match i % period {
+            0 => vec![(action, true)],
+            1 => vec![(action, false)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Receives the inputs sent until both peers have confirmed the same frames.
+    fn settle(host: &mut Peer, guest: &mut Peer) {
+        let target = host.sent().min(guest.sent());
+        for _ in 0..1000 {
+            if host.session.confirmed >= target && guest.session.confirmed >= target {
+                return;
+            }
+            for peer in [&mut *host, &mut *guest] {
+                peer.session.connection.flush().unwrap();
+                peer.session.advance(&mut peer.local, &mut peer.remote).unwrap();
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("the peers don't confirm frame {target}");
+    }
+
+    /// Checks that each peer simulated the same games as the other one, at the same frame.
+    fn assert_same_games(host: &Peer, guest: &Peer) {
+        assert_eq!(host.session.confirmed, guest.session.confirmed);
+        let [host_local, host_remote] = &host.session.snapshot;
+        let [guest_local, guest_remote] = &guest.session.snapshot;
+        assert_eq!(host_local.checksum(), guest_remote.checksum());
+        assert_eq!(host_remote.checksum(), guest_local.checksum());
+    }
+
+    #[test]
+    fn guest_plays_with_the_settings_of_the_host() {
+        // a free port, for the host to listen on
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let host_options = Options {
+            delay: 3,
+            handling: Handling { das: Duration::from_millis(100), ..Handling::default() },
+            delays: Delays { are: 10, line_clear: 20 },
+            ..Options::default()
+        };
+        let host = thread::spawn(move || Session::host(address, host_options));
+        let guest = loop {
+            match Session::join(address, Options { delay: 5, ..Options::default() }) {
+                Err(Error::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused => thread::sleep(Duration::from_millis(10)),
+                guest => break guest.unwrap(),
+            }
+        };
+        let host = host.join().unwrap().unwrap();
+        assert_eq!(guest.seed, host.seed);
+        assert_eq!(guest.delay, 3);
+        let ([host_local, host_remote], [guest_local, guest_remote]) = (host.games(), guest.games());
+        assert_eq!(guest_remote.handling, host_options.handling);
+        assert_eq!(host_remote.handling, Handling::default());
+        for game in [&host_local, &host_remote, &guest_local, &guest_remote] {
+            assert_eq!(game.delays, host_options.delays);
+        }
+        assert_eq!(host_local.checksum(), guest_remote.checksum());
+    }
+
+    #[test]
+    fn peers_simulate_the_same_games() {
+        use Action::*;
+        let (mut host, mut guest) = pair(Options::default());
+        for i in 0..150 {
+            host.frame(&inputs(i, 10, &[MoveLeft, HardDrop, RotateClockwise, HardDrop])).unwrap();
+            guest.frame(&inputs(i, 7, &[MoveRight, HardDrop, RotateCounterClockwise])).unwrap();
+        }
+        settle(&mut host, &mut guest);
+        assert!(host.session.confirmed > CHECKSUM_INTERVAL, "the checksums were compared");
+        assert_same_games(&host, &guest);
+        assert!(host.session.snapshot[0].stats.pieces > 0);
+    }
+
+    #[test]
+    fn disconnection_is_reported() {
+        let (mut host, guest) = pair(Options::default());
+        drop(guest);
+        let result = (0..100).find_map(|_| {
+            thread::sleep(Duration::from_millis(1));
+            host.frame(&[]).err()
+        });
+        assert!(matches!(result, Some(Error::Disconnected)), "{result:?}");
+    }
+
+    #[test]
+    fn desync_is_detected_at_the_next_checksum() {
+        let (mut host, mut guest) = pair(Options::default());
+        // the host's copy of the guest's game differs from the guest's one
+        host.session.snapshot[1].score += 100;
+        let result = (0..CHECKSUM_INTERVAL * 2).find_map(|_| {
+            thread::sleep(Duration::from_millis(1));
+            host.frame(&[]).err().or(guest.frame(&[]).err())
+        });
+        assert!(matches!(result, Some(Error::Desync(CHECKSUM_INTERVAL))), "{result:?}");
+    }
+
+    /// Plays 200 frames where the inputs of the host are sent 6 frames late, so that the guest
+    /// simulates ahead of them.
+    fn play_with_late_inputs(options: Options) -> (Peer, Peer) {
+        use Action::*;
+        let (mut host, mut guest) = pair(options);
+        let mut late = VecDeque::new();
+        for i in 0..200 {
+            late.push_back(inputs(i, 6, &[MoveLeft, HardDrop, RotateClockwise, MoveRight, HardDrop]));
+            if late.len() > 6 {
+                host.frame(&late.pop_front().unwrap()).unwrap();
+            }
+            guest.frame(&inputs(i, 9, &[HardDrop, MoveRight, HardDrop])).unwrap();
+            assert!(guest.session.ahead() <= options.window, "the guest simulates past its window");
+        }
+        for inputs in late {
+            host.frame(&inputs).unwrap();
+        }
+        settle(&mut host, &mut guest);
+        (host, guest)
+    }
+
+    #[test]
+    fn mispredictions_are_rolled_back() {
+        let (host, guest) = play_with_late_inputs(Options::default());
+        assert!(guest.session.rollback_frames > 0, "the guest predicted the host's inputs");
+        assert_same_games(&host, &guest);
+    }
+
+    #[test]
+    fn shown_games_catch_up_with_the_confirmed_ones() {
+        let (mut host, mut guest) = play_with_late_inputs(Options::default());
+        // the frames left are played without inputs on both sides
+        let target = host.sent().max(guest.sent());
+        for peer in [&mut host, &mut guest] {
+            while peer.sent() < target {
+                peer.session.tick().unwrap();
+            }
+        }
+        settle(&mut host, &mut guest);
+        for peer in [&host, &guest] {
+            let [local, remote] = &peer.session.snapshot;
+            assert_eq!(peer.session.ahead(), 0);
+            assert_eq!((peer.local.checksum(), peer.remote.checksum()), (local.checksum(), remote.checksum()));
+        }
+    }
+
+    #[test]
+    fn lockstep_never_rolls_back() {
+        let (host, guest) = play_with_late_inputs(Options { window: 0, ..Options::default() });
+        assert_eq!(host.session.rollback_frames, 0);
+        assert_eq!(guest.session.rollback_frames, 0);
+        assert_same_games(&host, &guest);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Error;
+use game::{Action, Delays, Handling};
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+/// Version of the protocol, both peers must use the same.
+pub(crate) const VERSION: u32 = 3;
+
+/// An action pressed (`true`) or released (`false`) by a player.
+pub type Input = (Action, bool);
+
+/// A message between two peers, sent as a line of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Message {
+    /// Sent by the host once the guest connects: the seed of the games, the input delay
+    /// and the delays of both games.
+    Hello { version: u32, seed: u64, delay: u32, delays: Delays },
+    /// Sent by both peers after the `Hello`: the handling of the sender's game.
+    Handling(Handling),
+    /// The inputs of the sender for a frame (possibly none).
+    Inputs { frame: u32, inputs: Vec<Input> },
+    /// The checksum of the games of the sender after simulating a frame.
+    Checksum { frame: u32, checksum: u64 },
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hello { version, seed, delay, delays } => {
+                write!(f, "HELLO {version} {seed} {delay} {} {}", delays.are, delays.line_clear)
+            }
+            Self::Handling(handling) => {
+                write!(f, "HANDLING {} {} {}", handling.das.as_nanos(), handling.arr.as_nanos(), handling.sdf)
+            }
+            Self::Inputs { frame, inputs } => {
+                write!(f, "INPUTS {frame}")?;
+                for (action, pressed) in inputs {
+                    write!(f, " {}{}", if *pressed { '+' } else { '-' }, action.letter())?;
+                }
+                Ok(())
+            }
+            Self::Checksum { frame, checksum } => write!(f, "SUM {frame} {checksum}"),
+        }
+    }
+}
+
+impl Message {
+    /// Parses a line written by `Display`.
+    pub(crate) fn parse(line: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidMessage(line.to_string());
+        let mut words = line.split_whitespace();
+        let keyword = words.next();
+        let mut number = || words.next().and_then(|word| word.parse::<u64>().ok()).ok_or_else(invalid);
+        let message = match keyword {
+            Some("HELLO") => {
+                let (version, seed, delay) = (number()? as u32, number()?, number()? as u32);
+                // the hello of another version is only read up to its version, it is rejected
+                let delays = if version == VERSION {
+                    Delays { are: number()? as u32, line_clear: number()? as u32 }
+                } else {
+                    Delays::NONE
+                };
+                Self::Hello { version, seed, delay, delays }
+            }
+            Some("HANDLING") => Self::Handling(Handling {
+                das: Duration::from_nanos(number()?),
+                arr: Duration::from_nanos(number()?),
+                sdf: number()? as u32,
+            }),
+            Some("INPUTS") => {
+                let frame = number()? as u32;
+                let inputs = line
+                    .split_whitespace()
+                    .skip(2)
+                    .map(|word| {
+                        let mut chars = word.chars();
+                        let pressed = match chars.next()? {
+                            '+' => true,
+                            '-' => false,
+                            _ => return None,
+                        };
+                        Some((Action::from_letter(chars.next()?)?, pressed))
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(invalid)?;
+                Self::Inputs { frame, inputs }
+            }
+            Some("SUM") => Self::Checksum {
+                frame: number()? as u32,
+                checksum: number()?,
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(message)
+    }
+}
@@ -0,0 +1,33 @@
+use rand::random_range;
+use std::time::Duration;
+
+/// Bad network conditions simulated on the messages a peer sends, to test online play locally.
+///
+/// TCP never loses messages: a lost packet is sent again, so it arrives (with the ones after it)
+/// a retransmission timeout later.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Simulation {
+    /// Delay added to every message.
+    pub latency: Duration,
+    /// Random delay added on top of `latency`, up to this one.
+    pub jitter: Duration,
+    /// Chance (0 to 1) that a message is lost and sent again.
+    pub loss: f64,
+}
+
+impl Simulation {
+    /// Time TCP waits before sending a lost packet again.
+    const RETRANSMISSION: Duration = Duration::from_millis(200);
+
+    /// Returns the delay to add to a message.
+    pub(crate) fn delay(&self) -> Duration {
+        let mut delay = self.latency;
+        if !self.jitter.is_zero() {
+            delay += self.jitter.mul_f64(random_range(0.0..1.0));
+        }
+        if self.loss > 0.0 && random_range(0.0..1.0) < self.loss {
+            delay += Self::RETRANSMISSION;
+        }
+        delay
+    }
+}
@@ -6,9 +6,14 @@ pub mod tetromino;
 pub use tetromino::Tetromino;
 use vecmath::{Direction, Point};
 
+/// A color as its red, green and blue components (frontends convert it, e.g. into SDL's `Color`).
+///
+/// The colors of the pieces come from the theme (see `config::Theme`).
+pub type Rgb = (u8, u8, u8);
+
 /// Each type of pieces of the `Tetris` game.
-#[derive(Debug, Clone, Copy, Default)]
-enum TetrominoKind {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TetrominoKind {
     O,
     #[default]
     I,
@@ -20,6 +25,13 @@ enum TetrominoKind {
 }
 
 impl TetrominoKind {
+    /// Every kind, in the order of the colors and the block textures of the themes.
+    pub const ALL: [Self; 7] = [Self::I, Self::J, Self::L, Self::O, Self::S, Self::Z, Self::T];
+
+    /// Returns the index of `self` in `ALL`.
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|&kind| kind == self).unwrap()
+    }
     /// `Up` facing of each tetromino.
     fn basic_shape(&self) -> [Point; 4] {
         match self {
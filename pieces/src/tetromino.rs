@@ -1,9 +1,8 @@
 use crate::TetrominoKind as Kind;
 
-use sdl2::pixels::Color;
 use vecmath::{Rotation, Direction, Point};
 
-use rand::seq::SliceRandom;
+use rand::{Rng, seq::SliceRandom};
 
 /// A tetromino is a shape that can be placed on the board.
 #[derive(Debug, Clone, Default)]
@@ -19,7 +18,7 @@ impl Tetromino {
     /// Constructor of `Tetromino`
     /// # Args
     /// `kind` is the type of Tetromino constructed.
-    fn new(kind: Kind) -> Self {
+    pub fn new(kind: Kind) -> Self {
         let offset = match kind {
             Kind::I => Point::new(2, -1),
             _ => Point::new(3, -1),
@@ -32,8 +31,8 @@ impl Tetromino {
         }
     }
     /// # Returns
-    /// an array of all the 7 variants shuffled
-    pub fn bag() -> [Tetromino; Self::SIZE] {
+    /// an array of all the 7 variants shuffled by `rng`
+    pub fn bag(rng: &mut impl Rng) -> [Tetromino; Self::SIZE] {
         let mut pieces = [
             Tetromino::new(Kind::O),
             Tetromino::new(Kind::I),
@@ -43,20 +42,18 @@ impl Tetromino {
             Tetromino::new(Kind::L),
             Tetromino::new(Kind::J),
         ];
-        pieces.shuffle(&mut rand::rng());
+        pieces.shuffle(rng);
         pieces
     }
-    /// Returns the color of `self` (each tetromino has its own color)
-    pub fn get_color(&self) -> Color {
-        match self.kind {
-            Kind::I => Color::RGB(0, 231, 254),     // LIGHT BLUE
-            Kind::J => Color::BLUE,                        // BLUE
-            Kind::L => Color::RGB(255, 127, 0),     // ORANGE
-            Kind::O => Color::RGB(255, 200, 0),     // YELLOW
-            Kind::S => Color::GREEN,                       // GREEN
-            Kind::Z => Color::RED,                         // RED
-            Kind::T => Color::MAGENTA,                     // MAGENTA
-        }
+    /// Returns the kind of `self`.
+    #[inline(always)]
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+    /// Returns the direction `self` is facing.
+    #[inline(always)]
+    pub fn direction(&self) -> Direction {
+        self.direction
     }
 
     /// Drops `self`'s y **in place** by 1.
@@ -1,13 +1,90 @@
 //! # Tetris
 //! Entry point of the program
 
-use engine::Engine;
-use game::Game;
-use std::error::Error as StdError;
+/// Play in an SDL window
+#[cfg(feature = "window")]
+mod window;
+
+use config::Config;
+use game::{Game, Mode};
+use tui::Tui;
+use std::{
+    error::Error as StdError,
+    path::{Path, PathBuf},
+};
+
+/// Port the spectators connect to when `--spectate` has no address.
+const SPECTATOR_PORT: u16 = 7879;
 
 fn main() -> Result<(), Box<dyn StdError>> {
-    let game = Game::new();
-    let mut engine = Engine::new(game);
-    
-    engine.run()
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // `--tui` plays in the terminal instead of a window
+    let tui = args.iter().position(|arg| arg == "--tui").map(|i| args.remove(i)).is_some();
+    // `--spectate [address]` can be given with any mode
+    let spectate = args.iter().position(|arg| arg == "--spectate").map(|i| {
+        args.remove(i);
+        match args.get(i).filter(|arg| !arg.starts_with("--")) {
+            Some(_) => with_port(&args.remove(i), SPECTATOR_PORT),
+            None => format!("127.0.0.1:{SPECTATOR_PORT}"),
+        }
+    });
+    let mut config = Config::load().map_err(|err| match Config::path() {
+        Some(path) => format!("invalid config {}: {err}", path.display()),
+        None => format!("invalid config: {err}"),
+    })?;
+    // `--font <path>` replaces the font of the config
+    match args.iter().position(|arg| arg == "--font") {
+        Some(i) if i + 1 < args.len() => {
+            args.remove(i);
+            config.font = Some(PathBuf::from(args.remove(i)));
+        }
+        Some(_) => return Err("missing path of --font".into()),
+        None => {}
+    }
+    let theme = config.load_theme().map_err(|err| match &config.theme {
+        Some(name) => format!("invalid theme {name:?}: {err}"),
+        None => err.to_string(),
+    })?;
+    // `--screenshot <fumen or file> [image]` saves images of boards instead of playing
+    if let Some(i) = args.iter().position(|arg| arg == "--screenshot") {
+        let Some(input) = args.get(i + 1) else {
+            return Err("missing fumen or file of --screenshot".into());
+        };
+        let output = Path::new(args.get(i + 2).map_or("board.png", String::as_str));
+        #[cfg(feature = "window")]
+        return window::save_boards(input, output, config, &theme);
+        #[cfg(not(feature = "window"))]
+        return Err(format!("cannot save {} from {input}: built without the `window` feature", output.display()).into());
+    }
+    // built without the window, the games are played in the terminal
+    if !tui {
+        #[cfg(feature = "window")]
+        return window::play(&args, config, theme, spectate);
+    }
+    match args.first().map(String::as_str) {
+        Some("host" | "join") => Err("online versus can't be played in the terminal".into()),
+        _ => match parse_mode(&args)? {
+            Mode::Versus => Err("versus can't be played in the terminal".into()),
+            _ if spectate.is_some() => Err("spectators can't watch a game in the terminal".into()),
+            mode => {
+                let mut tui = Tui::new(Game::new(mode));
+                tui.configure(config, theme);
+                Ok(tui.run()?)
+            }
+        },
+    }
+}
+
+/// Parses the mode and its options from the arguments (see `Mode::from_str`).
+fn parse_mode(args: &[String]) -> Result<Mode, Box<dyn StdError>> {
+    Ok(args.join(" ").parse()?)
+}
+
+/// Adds `port` to `address` if it has none.
+fn with_port(address: &str, port: u16) -> String {
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{address}:{port}")
+    }
 }
@@ -0,0 +1,103 @@
+use crate::{parse_mode, with_port};
+use config::{Config, Theme};
+use engine::{Engine, screenshot};
+use game::{Game, Mode, Replay};
+use net::{Options, Session, Simulation};
+use std::{
+    error::Error as StdError,
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+/// Plays in a window what `args` asks: an online versus (`host` or `join`), a mode, or the title
+/// without one, streaming its events to the spectators on `spectate` if given.
+pub(crate) fn play(args: &[String], config: Config, theme: Theme, spectate: Option<String>) -> Result<(), Box<dyn StdError>> {
+    let mut engine = match args.first().map(String::as_str) {
+        Some(command @ ("host" | "join")) => {
+            // the address is optional, the options follow it
+            let address = args.get(1).filter(|arg| !arg.starts_with("--"));
+            let options = Options {
+                handling: config.handling.into(),
+                delays: config.delays.into(),
+                ..parse_options(&args[1 + address.is_some() as usize..])?
+            };
+            let session = if command == "host" {
+                let address = with_port(address.map_or("0.0.0.0", String::as_str), net::DEFAULT_PORT);
+                println!("Waiting for a player on {address}...");
+                Session::host(address, options)?
+            } else {
+                let address = with_port(address.map_or("127.0.0.1", String::as_str), net::DEFAULT_PORT);
+                println!("Joining {address}...");
+                Session::join(address, options)?
+            };
+            Engine::online(session)
+        }
+        // without a mode, the player chooses one on the title
+        None => Engine::title(),
+        _ => match parse_mode(args)? {
+            Mode::Versus => Engine::versus(Game::new(Mode::Versus), Game::new(Mode::Versus)),
+            mode => Engine::new(Game::new(mode)),
+        },
+    };
+
+    engine.configure(config)?;
+    engine.set_theme(theme);
+    if let Some(address) = spectate {
+        engine.spectate(&address)?;
+        println!("Spectators can connect on {address}");
+    }
+
+    engine.run()
+}
+
+/// Saves images of the boards of `input` at `output`: the end of the game of a replay file, or
+/// each page of a fumen given as is or in a file (`<output>-<page>.png` if it has several).
+pub(crate) fn save_boards(input: &str, output: &Path, mut config: Config, theme: &Theme) -> Result<(), Box<dyn StdError>> {
+    let text = fs::read_to_string(input).unwrap_or_else(|_| input.to_string());
+    if let Ok(replay) = text.parse::<Replay>() {
+        screenshot::save(&[&replay.play_out()], &config, theme, output)?;
+        println!("Saved {}", output.display());
+        return Ok(());
+    }
+    let pages = fumen::decode(text.trim())?;
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let numbered = pages.len() > 1;
+    let preview = config.preview;
+    for (i, page) in pages.into_iter().enumerate() {
+        // only the queue of the page is shown, not random pieces after it
+        config.preview = preview.min(page.queue.len());
+        let current = page.current.clone();
+        let mut game = Game::from(page);
+        game.cur_tetromino = current;
+        let path = if numbered { output.with_file_name(format!("{stem}-{}.png", i + 1)) } else { output.to_path_buf() };
+        screenshot::save(&[&game], &config, theme, &path)?;
+        println!("Saved {}", path.display());
+    }
+    Ok(())
+}
+
+/// Parses the options of an online versus (`--delay`, `--window`, and `--latency`, `--jitter`
+/// and `--loss` to simulate a bad network).
+fn parse_options(args: &[String]) -> Result<Options, Box<dyn StdError>> {
+    let mut options = Options::default();
+    let mut simulation = Simulation::default();
+    for pair in args.chunks(2) {
+        let [option, value] = pair else {
+            return Err(format!("missing value of {}", pair[0]).into());
+        };
+        let number = || value.parse::<u32>().map_err(|_| format!("invalid value of {option}: {value}"));
+        match option.as_str() {
+            "--delay" => options.delay = number()?,
+            "--window" => options.window = number()?,
+            "--latency" => simulation.latency = Duration::from_millis(number()?.into()),
+            "--jitter" => simulation.jitter = Duration::from_millis(number()?.into()),
+            "--loss" => simulation.loss = number()?.min(100) as f64 / 100.0,
+            _ => return Err(format!("unknown option: {option} (expected `--delay`, `--window`, `--latency`, `--jitter` or `--loss`)").into()),
+        }
+    }
+    if simulation != Simulation::default() {
+        options.simulation = Some(simulation);
+    }
+    Ok(options)
+}
@@ -0,0 +1,170 @@
+use config::Keys;
+use crossterm::event::{KeyCode, KeyModifiers, ModifierKeyCode};
+use game::Action;
+use std::time::{Duration, Instant};
+
+/// Longest time between two presses of a key held in a terminal which doesn't report releases:
+/// longer than the interval of the key repeat of the terminals, shorter than a DAS.
+const HOLD: Duration = Duration::from_millis(100);
+
+/// The keys of the terminal bound to each action.
+#[derive(Default)]
+pub(crate) struct Bindings(Vec<(KeyCode, Action)>);
+
+impl Bindings {
+    /// Binds the keys of `keys` (SDL names, see `key_code`) which the terminal has.
+    pub(crate) fn new(keys: &Keys) -> Self {
+        Self(
+            keys.iter()
+                .into_iter()
+                .flat_map(|(action, _, names)| names.iter().filter_map(move |name| Some((key_code(name)?, action))))
+                .collect(),
+        )
+    }
+    /// Returns the action bound to a key of the terminal, `None` if there isn't one.
+    pub(crate) fn action(&self, code: KeyCode) -> Option<Action> {
+        // letters are bound whatever their case
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        self.0.iter().find(|(key, _)| *key == code).map(|(_, action)| *action)
+    }
+}
+
+/// Returns the key of the terminal with the SDL name `name`, `None` if the terminal has none.
+///
+/// The modifiers (e.g. `Right Ctrl`) are only reported on their own by terminals with the
+/// keyboard enhancement (see `crossterm::terminal::supports_keyboard_enhancement`).
+fn key_code(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+    Some(match name.to_ascii_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "return" => KeyCode::Enter,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "left shift" => KeyCode::Modifier(ModifierKeyCode::LeftShift),
+        "right shift" => KeyCode::Modifier(ModifierKeyCode::RightShift),
+        "left ctrl" => KeyCode::Modifier(ModifierKeyCode::LeftControl),
+        "right ctrl" => KeyCode::Modifier(ModifierKeyCode::RightControl),
+        "left alt" => KeyCode::Modifier(ModifierKeyCode::LeftAlt),
+        "right alt" => KeyCode::Modifier(ModifierKeyCode::RightAlt),
+        name => KeyCode::F(name.strip_prefix('f')?.parse().ok().filter(|n| (1..=24).contains(n))?),
+    })
+}
+
+/// The keys held in a terminal which doesn't report releases, where a key held sends presses
+/// over and over (its repeats): it's held while they keep coming.
+///
+/// The first repeat comes after the repeat delay of the terminal, longer than `HOLD`, so it's
+/// taken for another press.
+#[derive(Default)]
+pub(crate) struct Held(Vec<(Action, Instant)>);
+
+impl Held {
+    /// Handles a press of the key of `action` at `now`.
+    /// # Returns
+    /// `true` if it's a new press, `false` if it's a repeat of the key held.
+    pub(crate) fn press(&mut self, action: Action, now: Instant) -> bool {
+        match self.0.iter_mut().find(|(held, _)| *held == action) {
+            Some((_, last)) if now.duration_since(*last) < HOLD => {
+                *last = now;
+                false
+            }
+            Some((_, last)) => {
+                *last = now;
+                true
+            }
+            None => {
+                self.0.push((action, now));
+                true
+            }
+        }
+    }
+    /// Returns the actions whose keys aren't held anymore at `now` (not pressed for `HOLD`).
+    pub(crate) fn released(&mut self, now: Instant) -> Vec<Action> {
+        let mut released = Vec::new();
+        self.0.retain(|&(action, last)| {
+            let held = now.duration_since(last) < HOLD;
+            if !held {
+                released.push(action);
+            }
+            held
+        });
+        released
+    }
+}
+
+/// Returns `true` if the key quits the game (Escape or Ctrl+C, raw mode doesn't send signals).
+pub(crate) fn is_quit(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    code == KeyCode::Esc || (code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Bindings as Config;
+
+    #[test]
+    fn sdl_names_are_keys_of_the_terminal() {
+        assert_eq!(key_code("A"), Some(KeyCode::Char('a')));
+        assert_eq!(key_code("/"), Some(KeyCode::Char('/')));
+        assert_eq!(key_code("Space"), Some(KeyCode::Char(' ')));
+        assert_eq!(key_code("Left"), Some(KeyCode::Left));
+        assert_eq!(key_code("Right Ctrl"), Some(KeyCode::Modifier(ModifierKeyCode::RightControl)));
+        assert_eq!(key_code("F5"), Some(KeyCode::F(5)));
+        assert_eq!(key_code("F99"), None);
+        assert_eq!(key_code("Keypad Enter"), None);
+    }
+
+    #[test]
+    fn keys_of_the_config_are_bound() {
+        let bindings = Bindings::new(&Config::default().single);
+        assert_eq!(bindings.action(KeyCode::Left), Some(Action::MoveLeft));
+        assert_eq!(bindings.action(KeyCode::Char('a')), Some(Action::MoveLeft));
+        assert_eq!(bindings.action(KeyCode::Char('A')), Some(Action::MoveLeft));
+        assert_eq!(bindings.action(KeyCode::Char(' ')), Some(Action::HardDrop));
+        assert_eq!(bindings.action(KeyCode::Modifier(ModifierKeyCode::LeftControl)), Some(Action::RotateCounterClockwise));
+        assert_eq!(bindings.action(KeyCode::Char('z')), None);
+    }
+
+    #[test]
+    fn repeats_of_a_key_held_are_not_presses() {
+        let mut held = Held::default();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        assert!(held.press(Action::MoveLeft, at(0)));
+        assert!(held.released(at(50)).is_empty());
+        // the terminal's repeat delay, then its repeats
+        assert!(held.press(Action::MoveLeft, at(500)));
+        for millis in (530..1000).step_by(30) {
+            assert!(!held.press(Action::MoveLeft, at(millis)), "{millis} ms");
+            assert!(held.released(at(millis + 10)).is_empty());
+        }
+        assert_eq!(held.released(at(1100)), [Action::MoveLeft]);
+        assert!(held.press(Action::MoveLeft, at(1200)));
+    }
+
+    #[test]
+    fn taps_are_presses() {
+        let mut held = Held::default();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        assert!(held.press(Action::MoveRight, at(0)));
+        assert!(held.press(Action::HardDrop, at(20)));
+        assert_eq!(held.released(at(110)), [Action::MoveRight]);
+        assert!(held.press(Action::MoveRight, at(150)));
+    }
+}
@@ -0,0 +1,137 @@
+//! # Tui
+//! A frontend which plays the game in a terminal (e.g. over SSH), with ANSI colors and
+//! Unicode blocks instead of an SDL window.
+
+/// Map the keys of the terminal to the actions
+mod input;
+/// Draw the game in the terminal
+mod render;
+
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{
+        self,
+        Event,
+        KeyEventKind,
+        KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use config::{Config, Theme};
+use game::Game;
+use input::{Bindings, Held};
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub struct Tui {
+    game: Game,
+    /// Settings of the player.
+    config: Config,
+    /// Keys of the config the terminal has (the ones of a player playing alone).
+    bindings: Bindings,
+    /// Theme of the config, of which the terminal uses the colors of the blocks.
+    theme: Theme,
+    /// `true` if the terminal reports the releases of the keys.
+    releases: bool,
+    /// The keys held, when the terminal doesn't report releases.
+    held: Held,
+}
+
+impl Tui {
+    pub fn new(game: Game) -> Self {
+        Self {
+            game,
+            bindings: Bindings::new(&Config::default().bindings.single),
+            config: Config::default(),
+            theme: Theme::default(),
+            releases: false,
+            held: Held::default(),
+        }
+    }
+    /// Plays with the keys of a player playing alone, the handling, the delays, the next pieces
+    /// shown, the ghost and the grid of `config`, and the colors of the blocks of its `theme`.
+    pub fn configure(&mut self, config: Config, theme: Theme) {
+        self.bindings = Bindings::new(&config.bindings.single);
+        self.game.handling = config.handling.into();
+        self.game.delays = config.delays.into();
+        self.config = config;
+        self.theme = theme;
+    }
+    /// Returns a new game in the same mode, with the handling and the delays of the config.
+    fn new_game(&self) -> Game {
+        let mut game = Game::new(self.game.mode);
+        game.handling = self.config.handling.into();
+        game.delays = self.config.delays.into();
+        game
+    }
+    /// Runs the game in the terminal until Escape is pressed.
+    ///
+    /// The terminal is restored even if drawing fails.
+    pub fn run(&mut self) -> io::Result<()> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        self.releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if self.releases {
+            // the modifiers are bound on their own too
+            let flags = KeyboardEnhancementFlags::REPORT_EVENT_TYPES | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES;
+            execute!(out, PushKeyboardEnhancementFlags(flags))?;
+        }
+
+        let result = self.play(&mut out);
+
+        if self.releases {
+            execute!(out, PopKeyboardEnhancementFlags)?;
+        }
+        execute!(out, Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+    fn play(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let mut last_frame = Instant::now();
+        // time not simulated yet
+        let mut lag = Duration::ZERO;
+        loop {
+            while event::poll(Duration::ZERO)? {
+                let Event::Key(key) = event::read()? else { continue };
+                if input::is_quit(key.code, key.modifiers) {
+                    return Ok(());
+                }
+                match (key.kind, self.bindings.action(key.code)) {
+                    (KeyEventKind::Release, Some(action)) => self.game.release(action),
+                    (KeyEventKind::Release, None) => {}
+                    (_, _) if key.code == event::KeyCode::Enter && self.game.outcome.is_some() => {
+                        self.game = self.new_game();
+                    }
+                    // the game repeats the held keys itself (see `Handling`)
+                    (KeyEventKind::Repeat, _) => {}
+                    // without releases, the repeats of the terminal come as presses
+                    (_, Some(action)) if self.releases || self.held.press(action, Instant::now()) => {
+                        self.game.update(action);
+                    }
+                    (_, _) => {}
+                }
+            }
+            for action in self.held.released(Instant::now()) {
+                self.game.release(action);
+            }
+
+            lag += last_frame.elapsed();
+            last_frame = Instant::now();
+            while lag >= Game::FRAME {
+                self.game.step();
+                lag -= Game::FRAME;
+            }
+
+            render::draw(out, &self.game, &self.config, &self.theme)?;
+
+            thread::sleep(Duration::from_millis(16));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+use config::{Config, Theme, MAX_PREVIEW};
+use game::{Game, Mode, Outcome};
+use matrix::{Block, Matrix};
+use pieces::{Rgb, TetrominoKind as Kind};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+/// Columns of the terminal taken by a cell.
+const CELL_WIDTH: u16 = 2;
+/// Column of the panel right of the matrix.
+const PANEL: u16 = Matrix::WIDTH as u16 * CELL_WIDTH + 4;
+const BORDER_COLOR: Color = Color::DarkGrey;
+
+/// What a cell of the matrix shows.
+#[derive(Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    /// A block of the color, with the kind of its tetromino if its pattern is shown.
+    Block(Rgb, Option<Kind>),
+    Ghost(Rgb),
+}
+
+/// Draws the matrix with the current piece and its ghost, the score and the queue of `game`.
+pub(crate) fn draw(out: &mut impl Write, game: &Game, config: &Config, theme: &Theme) -> io::Result<()> {
+    let colors = config.palette.colors(theme);
+    let pattern = |kind: Kind| Some(kind).filter(|_| config.patterns);
+    let mut cells = [[Cell::Empty; Matrix::WIDTH]; Matrix::HEIGHT];
+    for (y, row) in game.matrix.iter().enumerate() {
+        for (x, block) in row.iter().enumerate() {
+            match block {
+                Some(block @ Block::Piece(kind, _)) => cells[y][x] = Cell::Block(colors.block(*block), pattern(*kind)),
+                Some(block) => cells[y][x] = Cell::Block(colors.block(*block), None),
+                None => {}
+            }
+        }
+    }
+    // the rows being cleared are white
+    for &y in &game.clearing {
+        cells[y] = [Cell::Block((255, 255, 255), None); Matrix::WIDTH];
+    }
+    // target of the finesse trainer, under the ghost
+    if let Some(target) = &game.target {
+        for coord in target.position() {
+            cells[coord.y as usize][coord.x as usize] = Cell::Ghost(colors.piece(target.kind()));
+        }
+    }
+    if let Some(cur) = &game.cur_tetromino {
+        let color = colors.piece(cur.kind());
+        let ghost = if config.ghost { game.get_ghost().unwrap().position().to_vec() } else { Vec::new() };
+        for (coord, cell) in ghost.into_iter().map(|p| (p, Cell::Ghost(color)))
+            .chain(cur.position().into_iter().map(|p| (p, Cell::Block(color, pattern(cur.kind())))))
+        {
+            if coord.y >= 0 && (coord.x as usize) < Matrix::WIDTH {
+                cells[coord.y as usize][coord.x as usize] = cell;
+            }
+        }
+    }
+
+    // matrix with its border
+    for (y, row) in cells.iter().enumerate() {
+        queue!(out, MoveTo(0, y as u16), SetForegroundColor(BORDER_COLOR), Print("│"))?;
+        for cell in row {
+            match cell {
+                Cell::Empty if config.grid => queue!(out, SetForegroundColor(BORDER_COLOR), Print(" ·"))?,
+                Cell::Empty => queue!(out, Print("  "))?,
+                Cell::Block(color, pattern) => print_block(out, *color, *pattern)?,
+                Cell::Ghost(color) => queue!(out, SetForegroundColor(rgb(*color)), Print("░░"))?,
+            }
+        }
+        queue!(out, SetForegroundColor(BORDER_COLOR), Print("│"))?;
+    }
+    let bottom = format!("└{}┘", "─".repeat(Matrix::WIDTH * CELL_WIDTH as usize));
+    queue!(out, MoveTo(0, Matrix::HEIGHT as u16), Print(bottom), ResetColor)?;
+
+    // panel
+    let mut lines = vec![
+        format!("SCORE {}", game.score),
+        format!("LINES {}", game.stats.lines),
+        format!("LEVEL {}", game.level),
+        format!("TIME  {}", format_time(game.stats.time())),
+        String::new(),
+        "NEXT".to_string(),
+    ];
+    if let Some(goal) = game.mode.goal() {
+        lines.insert(2, format!("LEFT  {}", goal.saturating_sub(game.stats.lines)));
+    }
+    if game.mode == Mode::Finesse {
+        lines[1] = format!("RETRY {}", game.stats.retries);
+        lines.insert(2, format!("LEFT  {}", Mode::FINESSE_PIECES.saturating_sub(game.stats.pieces - game.stats.retries)));
+    }
+    if let Some(limit) = game.mode.time_limit() {
+        lines[3] = format!("TIME  {}", format_time(limit.saturating_sub(game.stats.time())));
+    }
+    let top = lines.len() as u16;
+    for (i, line) in lines.iter().enumerate() {
+        queue!(out, MoveTo(PANEL, i as u16), Clear(ClearType::UntilNewLine), Print(line))?;
+    }
+    // each next piece takes 3 rows (its 2 rows of cells and a blank one)
+    for row in top..top + MAX_PREVIEW as u16 * 3 {
+        queue!(out, MoveTo(PANEL, row), Clear(ClearType::UntilNewLine))?;
+    }
+    for (i, next) in game.bag.iter().rev().take(config.preview).enumerate() {
+        for coord in next.cells {
+            queue!(out, MoveTo(PANEL + coord.x as u16 * CELL_WIDTH, top + i as u16 * 3 + coord.y as u16))?;
+            print_block(out, colors.piece(next.kind()), pattern(next.kind()))?;
+        }
+    }
+    queue!(out, ResetColor)?;
+
+    // results
+    let status = match game.outcome {
+        Some(Outcome::Completed) => "FINISHED  ENTER: RETRY  ESC: QUIT",
+        Some(Outcome::ToppedOut) => "GAME OVER  ENTER: RETRY  ESC: QUIT",
+        None if !game.started => "PRESS A KEY TO START",
+        None => "",
+    };
+    queue!(out, MoveTo(0, Matrix::HEIGHT as u16 + 1), Clear(ClearType::UntilNewLine), Print(status))?;
+    out.flush()
+}
+
+/// Prints a block of `color`, with the letter of `pattern` (dark on the color) if there's one.
+fn print_block(out: &mut impl Write, color: Rgb, pattern: Option<Kind>) -> io::Result<()> {
+    match pattern {
+        Some(kind) => queue!(
+            out,
+            SetBackgroundColor(rgb(color)),
+            SetForegroundColor(Color::Black),
+            Print(format!("{kind:?}{kind:?}")),
+            SetBackgroundColor(Color::Reset),
+        ),
+        None => queue!(out, SetForegroundColor(rgb(color)), Print("██")),
+    }
+}
+
+/// Converts a color of the game into a terminal one.
+fn rgb((r, g, b): Rgb) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// Formats `time` as `m:ss.mmm`.
+fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
@@ -5,7 +5,7 @@ use std::{
 };
 
 /// Struct that represents a 2D point used to index a matrix or a vector to move a point.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Point {
     pub x: isize,
     pub y: isize,
@@ -7,7 +7,7 @@ pub enum Rotation {
 }
 
 /// A type that represents the direction of a movement.
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub enum Direction {
     #[default]
     Up,